@@ -0,0 +1,71 @@
+use super::opcode::{JUMPDEST, PUSH1, PUSH32};
+
+#[derive(Debug, Clone)]
+// Valid jump destinations of code, as a bitmap indexed by code offset
+pub struct JumpTable {
+    bits: Vec<u64>,
+}
+impl JumpTable {
+    // Mark every JUMPDEST byte that is not part of a PUSH immediate
+    pub fn analyze(code: &[u8]) -> Self {
+        let mut bits = vec![0u64; code.len().div_ceil(64)];
+        let mut pc = 0;
+        while pc < code.len() {
+            match code[pc] {
+                op @ PUSH1..=PUSH32 => pc += (op - PUSH1) as usize + 1,
+                JUMPDEST => bits[pc / 64] |= 1 << (pc % 64),
+                _ => (),
+            };
+            pc += 1;
+        }
+        JumpTable { bits }
+    }
+
+    // Returns whether dest is a valid jump destination
+    pub fn is_valid(&self, dest: usize) -> bool {
+        self.bits
+            .get(dest / 64)
+            .is_some_and(|word| word & (1 << (dest % 64)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jumpdest_inside_push_data_is_invalid() {
+        // PUSH1 0x5B JUMPDEST
+        let table = JumpTable::analyze(&[PUSH1, JUMPDEST, JUMPDEST]);
+        assert!(!table.is_valid(0));
+        assert!(!table.is_valid(1));
+        assert!(table.is_valid(2));
+        // PUSH32 covering the JUMPDEST 32 bytes later, followed by a real one
+        let mut code = vec![PUSH32];
+        code.extend([JUMPDEST; 33]);
+        let table = JumpTable::analyze(&code);
+        assert!((0..=32).all(|dest| !table.is_valid(dest)));
+        assert!(table.is_valid(33));
+    }
+
+    #[test]
+    fn truncated_push_at_end_of_code() {
+        let table = JumpTable::analyze(&[JUMPDEST, PUSH32, JUMPDEST]);
+        assert!(table.is_valid(0));
+        assert!(!table.is_valid(2));
+        let table = JumpTable::analyze(&[PUSH1 + 1, JUMPDEST]);
+        assert!(!table.is_valid(1));
+    }
+
+    #[test]
+    fn target_beyond_end_of_code_is_invalid() {
+        let mut code = vec![0x00; 63];
+        code.push(JUMPDEST);
+        let table = JumpTable::analyze(&code);
+        assert!(table.is_valid(63));
+        assert!(!table.is_valid(64));
+        assert!(!table.is_valid(128));
+        assert!(!table.is_valid(usize::MAX));
+        assert!(!JumpTable::analyze(&[]).is_valid(0));
+    }
+}
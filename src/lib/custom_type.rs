@@ -1,8 +1,10 @@
 use ethnum::{u256, i256};
+use sha3::{Digest, Keccak256};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 // Big endian u256 type
 pub struct U256BE([u8; 32]);
+#[allow(clippy::should_implement_trait)]
 impl U256BE {
     // Return self as slice
     pub fn as_slice(self) -> [u8; 32] {
@@ -15,6 +17,17 @@ impl U256BE {
         usize::from_be_bytes(ret)
     }
 
+    // Convert self to usize, or None if the value does not fit
+    pub fn try_to_usize(self) -> Option<usize> {
+        if self.0[..24].iter().any(|b| *b != 0) { return None; };
+        Some(self.to_usize())
+    }
+
+    // Convert self to usize, clamping values that do not fit to usize::MAX
+    pub fn saturating_to_usize(self) -> usize {
+        self.try_to_usize().unwrap_or(usize::MAX)
+    }
+
     // Convert self to u160
     pub fn to_u160(self) -> U160 {
         U160(self.0[12..].try_into().unwrap())
@@ -36,6 +49,24 @@ impl U256BE {
         u32::from_be_bytes(ret)
     }
 
+    // Return number of significant bytes in self
+    pub fn byte_len(self) -> usize {
+        32 - self.0.iter().take_while(|b| **b == 0).count()
+    }
+
+    // EXP operator: self ** exponent, wrapping at 2^256
+    pub fn exp(self, exponent: U256BE) -> Self {
+        let mut base = self.to_u256();
+        let mut exponent = exponent.to_u256();
+        let mut ret = u256::ONE;
+        while exponent != u256::ZERO {
+            if exponent & u256::ONE == u256::ONE { ret = ret.wrapping_mul(base); };
+            base = base.wrapping_mul(base);
+            exponent >>= 1;
+        }
+        U256BE::from_u256(ret)
+    }
+
     // NOT bitwise operator: !self
     pub fn not(self) -> Self {
         let mut ret = [0u8; 32];
@@ -102,31 +133,29 @@ impl U256BE {
         } else { U256BE([0;32]) }
     }
 
-    // SHL operator: int256<self> << int256<value>
-    pub fn shl(self, value: U256BE) -> Self {  // Limited by u256 methods. Investigte alternative method(s)
-        let result = u256::from_be_bytes(self.0).overflowing_shl(u32::from_be_bytes(value.0[28..=31].try_into().unwrap()));
-        if result.1 { return U256BE::zero(); };
-        U256BE::from_u256(result.0)
+    // SHL operator: self << shift, zero for shifts of 256 or more
+    pub fn shl(self, shift: U256BE) -> Self {
+        match shift.try_to_usize() {
+            Some(n) if n < 256 => U256BE::from_u256(self.to_u256() << n as u32),
+            _ => U256BE::zero(),
+        }
     }
 
-    // SHR operator: int256<self> << int256<value>
-    pub fn shr(self, value: U256BE) -> Self { // Limited by u256 methods. Investigte alternative method(s)
-        let result = u256::from_be_bytes(self.0).overflowing_shr(u32::from_be_bytes(value.0[28..=31].try_into().unwrap()));
-        if result.1 { return U256BE::zero(); };
-        U256BE::from_u256(result.0)
-     }
-
-    // SAR operator: int256<self> >> int256<value>
-    pub fn sar(self, value: U256BE) -> Self { // Limited by i256 methods. Investigte alternative method(s)
-        let result = i256::from_be_bytes(self.0).overflowing_shr(u32::from_be_bytes(value.0[28..=31].try_into().unwrap()));
-        if result.1 { return U256BE::zero(); };
-        U256BE::from_i256(result.0)
+    // SHR operator: self >> shift, zero for shifts of 256 or more
+    pub fn shr(self, shift: U256BE) -> Self {
+        match shift.try_to_usize() {
+            Some(n) if n < 256 => U256BE::from_u256(self.to_u256() >> n as u32),
+            _ => U256BE::zero(),
+        }
     }
 
-    // Equivalence operator: self == value
-    pub fn eq(self, value: U256BE) -> bool {
-        if self.0 == value.0 { true }
-        else { false }
+    // SAR operator: int256<self> >> shift, sign-filled for shifts of 256 or more
+    pub fn sar(self, shift: U256BE) -> Self {
+        let value = self.to_i256();
+        match shift.try_to_usize() {
+            Some(n) if n < 256 => U256BE::from_i256(value >> n as u32),
+            _ => U256BE::from_i256(value >> 255),
+        }
     }
 
     // Equivalence operator: self == value, return U256BE 1/0
@@ -137,8 +166,7 @@ impl U256BE {
 
     // Returns whether self is equal to [0;32]
     pub fn is_zero(self) -> bool {
-        if self.0 == [0; 32] { true }
-        else { false }
+        self.0 == [0; 32]
     }
 
     // Returns whether self is equal to [0;32], return U256BE 1/0
@@ -188,12 +216,53 @@ impl U256BE {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 // Big endian u160 type
 pub struct U160([u8; 20]);
 impl U160 {
+    // Return self as slice
+    pub fn as_slice(self) -> [u8; 20] {
+        self.0
+    }
+
     // Convert self to U256BE
     pub fn to_u256_be(self) -> U256BE {
         U256BE::from_slice(&self.0)
     }
+
+    // Initialize U160 from slice, left-padding with zeroes
+    pub fn from_slice(slice: &[u8]) -> Self {
+        let mut ret: [u8; 20] = [0; 20];
+        ret[20 - slice.len()..].clone_from_slice(slice);
+        U160(ret)
+    }
+}
+
+// Keccak-256 digest of bytes
+pub fn keccak256(bytes: &[u8]) -> U256BE {
+    let mut hasher = Keccak256::default();
+    hasher.update(bytes);
+    U256BE::from_slice(&hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifts_take_value_below_shift() {
+        let one = U256BE::from_u8(1);
+        assert_eq!(one.shl(U256BE::from_u8(4)), U256BE::from_u8(16));
+        assert_eq!(U256BE::from_u8(16).shr(U256BE::from_u8(4)), one);
+        assert_eq!(one.shl(U256BE::from_usize(256)), U256BE::zero());
+        assert_eq!(one.shr(U256BE::from_usize(256)), U256BE::zero());
+    }
+
+    #[test]
+    fn sar_of_minus_one_by_256_keeps_sign() {
+        let minus_one = U256BE::from_i256(i256::MINUS_ONE);
+        assert_eq!(minus_one.sar(U256BE::from_usize(256)), minus_one);
+        assert_eq!(minus_one.sar(U256BE::from_u256(u256::MAX)), minus_one);
+        assert_eq!(U256BE::from_u8(1).sar(U256BE::from_usize(256)), U256BE::zero());
+    }
 }
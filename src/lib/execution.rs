@@ -1,14 +1,22 @@
-use super::custom_type::U256BE;
-use super::memory::Memory;
+use super::analysis::JumpTable;
+use super::custom_type::{keccak256, U160, U256BE};
+use super::memory::{Memory, MAX_MEMORY_SIZE};
+use super::message::{CallKind, Message};
 use super::opcode::*;
+use super::precompile::{Precompile, Precompiles};
+use super::result::{ExecutionResult, HaltReason};
+use super::spec::SpecId;
 use super::stack::Stack;
 use super::statuscode::StatusCode;
-use super::state::Block;
-use sha3::{Digest, Keccak256};
+use super::state::{Block, Log, TxEnv};
+use super::world_state::WorldState;
+use ethnum::u256;
+use std::rc::Rc;
 
 // EVM Execution Context
-pub struct ExecutionContext {
+pub struct ExecutionContext<'a> {
     code: Vec<u8>,
+    jump_table: Rc<JumpTable>,
     stack: Stack,
     memory: Memory,
     pc: usize,
@@ -16,31 +24,173 @@ pub struct ExecutionContext {
     stopped: bool,
     calldata: Vec<u8>,
     returndata: Vec<u8>,
-    block: Block
+    output: Vec<u8>,
+    block: Block,
+    env: Rc<TxEnv>,
+    precompiles: Rc<Precompiles>,
+    spec: SpecId,
+    state: &'a mut WorldState,
+    kind: CallKind,
+    address: U160,
+    code_address: U160,
+    caller: U160,
+    value: u256,
+    depth: usize,
+    is_static: bool,
+    gas_start: usize,
+    refund: isize,
+    // Whether a top-level run ends the transaction itself
+    ends_transaction: bool,
+    // Print each executed opcode with the resulting stack and memory
+    trace: bool,
 }
-impl ExecutionContext {
-    // Initialize execution context
-    pub fn init(code: Vec<u8>, stack: Stack, memory: Memory, gas_limit: usize) -> Self {
+impl<'a> ExecutionContext<'a> {
+    // Initialize execution context running the code of message.code_address, or message init code
+    pub fn init(
+        message: Message,
+        stack: Stack,
+        memory: Memory,
+        state: &'a mut WorldState,
+        spec: SpecId,
+    ) -> Self {
+        let (code, jump_table, calldata) = match message.is_create() {
+            true => {
+                let jump_table = Rc::new(JumpTable::analyze(&message.calldata));
+                (message.calldata, jump_table, Vec::new())
+            },
+            false => {
+                // Accounts with a delegation designator run the delegate's code (EIP-7702)
+                let code_address = state.delegation(message.code_address).unwrap_or(message.code_address);
+                let jump_table = state.jump_table(code_address);
+                (state.code(code_address).to_vec(), jump_table, message.calldata)
+            },
+        };
         ExecutionContext {
-            code: code,
-            stack: stack,
-            memory: memory,
+            code,
+            jump_table,
+            stack,
+            memory,
             pc: 0,
-            gas_limit: gas_limit,
+            gas_limit: message.gas,
             stopped: false,
-            calldata: Vec::with_capacity(1024),
-            returndata: Vec::with_capacity(1024),
-            block: Block::default()
+            calldata,
+            returndata: Vec::new(),
+            output: Vec::new(),
+            block: Block::default(),
+            env: Rc::new(TxEnv {
+                origin: message.caller,
+                ..TxEnv::default()
+            }),
+            // Top-level frames start with the fork's standard precompiles; child frames inherit their parent's
+            precompiles: match message.depth {
+                0 => Rc::new(Precompiles::for_spec(spec)),
+                _ => Rc::new(Precompiles::init()),
+            },
+            spec,
+            state,
+            kind: message.kind,
+            address: message.address,
+            code_address: message.code_address,
+            caller: message.caller,
+            value: message.value,
+            depth: message.depth,
+            is_static: message.is_static,
+            gas_start: message.gas,
+            refund: 0,
+            ends_transaction: true,
+            trace: false,
         }
     }
 
-    // Load 32-byte word from calldata at offset
-    pub fn calldata_load(&mut self, offset: usize) -> Result<Vec<u8>, StatusCode> {
-        let len_original = self.calldata.len();
-        if len_original <= offset + 31 { self.calldata.resize(offset + 32, 0); };
-        let ret = self.calldata[offset..offset + 32].to_vec();
-        self.calldata.truncate(len_original);
-        Ok(ret)
+    // Set block the execution runs in; the environment's chain id follows the block's
+    pub fn set_block(&mut self, block: Block) {
+        Rc::make_mut(&mut self.env).chain_id = block.chain_id;
+        self.block = block;
+    }
+
+    // Set transaction environment the execution runs in
+    pub fn set_env(&mut self, env: TxEnv) {
+        self.env = Rc::new(env);
+    }
+
+    // Set precompiles the execution and its sub-calls dispatch to
+    pub fn set_precompiles(&mut self, precompiles: Precompiles) {
+        self.precompiles = Rc::new(precompiles);
+    }
+
+    // Print opcodes executed by this execution and its sub-calls to stdout
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    // Leave ending the transaction to the caller of a top-level run, so fees can be paid first
+    pub fn defer_end_transaction(&mut self) {
+        self.ends_transaction = false;
+    }
+
+    // Return gas refund counter
+    pub fn refund(&self) -> isize {
+        self.refund
+    }
+
+    // Return remaining gas
+    pub fn gas_left(&self) -> usize {
+        self.gas_limit
+    }
+
+    // Charge SSTORE gas and update refund counter: net gas metering (EIP-2200) from Istanbul
+    pub fn sstore_gas(&mut self, key: U256BE, value: U256BE) -> Result<(), StatusCode> {
+        if self.is_static { return Err(StatusCode::StaticModeViolation); };
+        let slot = self.state.storage_slot(self.address, key);
+        let clears = sstore_clears_schedule(self.spec);
+        if !self.spec.is_enabled(SpecId::Istanbul) {
+            if !slot.current.is_zero() && value.is_zero() { self.refund += clears; };
+            let cost = if slot.current.is_zero() && !value.is_zero() { SSTORE_SET_GAS } else { SSTORE_RESET_GAS };
+            return self.sub_gas(cost);
+        };
+        if self.gas_limit <= SSTORE_SENTRY_GAS { return Err(StatusCode::OutOfGas); };
+        self.storage_access_gas(key, COLD_SLOAD_GAS)?;
+        let sload = sload_gas(self.spec);
+        let reset = sstore_reset_gas(self.spec);
+        if slot.current == value { return self.sub_gas(sload); };
+        if slot.original == slot.current {
+            if slot.original.is_zero() { return self.sub_gas(SSTORE_SET_GAS); };
+            if value.is_zero() { self.refund += clears; };
+            return self.sub_gas(reset);
+        };
+        if !slot.original.is_zero() {
+            if slot.current.is_zero() { self.refund -= clears; };
+            if value.is_zero() { self.refund += clears; };
+        };
+        if slot.original == value {
+            let restored = if slot.original.is_zero() { SSTORE_SET_GAS } else { reset };
+            self.refund += (restored - sload) as isize;
+        };
+        self.sub_gas(sload)
+    }
+
+    // Warm address, charging the cold surcharge on first access in the transaction (EIP-2929)
+    pub fn account_access_gas(&mut self, address: U160) -> Result<(), StatusCode> {
+        let cold = self.state.access_address(address);
+        if cold && self.spec.is_enabled(SpecId::Berlin) { return self.sub_gas(COLD_ACCOUNT_ACCESS_GAS - WARM_STORAGE_READ_GAS); };
+        Ok(())
+    }
+
+    // Warm storage key of the frame's account, charging cost on first access in the transaction (EIP-2929)
+    pub fn storage_access_gas(&mut self, key: U256BE, cost: usize) -> Result<(), StatusCode> {
+        let cold = self.state.access_storage(self.address, key);
+        if cold && self.spec.is_enabled(SpecId::Berlin) { return self.sub_gas(cost); };
+        Ok(())
+    }
+
+    // Load 32-byte word from calldata at offset, zero-padded past the end of calldata
+    pub fn calldata_load(&self, offset: usize) -> U256BE {
+        let mut word = [0u8; 32];
+        if offset < self.calldata.len() {
+            let end = self.calldata.len().min(offset.saturating_add(32));
+            word[..end - offset].copy_from_slice(&self.calldata[offset..end]);
+        };
+        U256BE::from_slice(&word)
     }
 
     // Deduct gas from limit
@@ -53,20 +203,21 @@ impl ExecutionContext {
     }
 
     // Halt execution
-    pub fn stop(&mut self) -> () {
+    pub fn stop(&mut self) {
         self.stopped = true
     }
 
-    // Set program counter to destination
-    pub fn pc_jump(&mut self, dest: usize) -> Result<(), StatusCode> {
-        if dest >= self.code.len() { return Err(StatusCode::BadJumpDest); };
+    // Set program counter to destination, which must be a JUMPDEST
+    pub fn pc_jump(&mut self, dest: U256BE) -> Result<(), StatusCode> {
+        let dest = dest.try_to_usize().ok_or(StatusCode::BadJumpDest)?;
+        if !self.jump_table.is_valid(dest) { return Err(StatusCode::BadJumpDest); };
         self.pc = dest;
         Ok(())
     }
 
     // Increment program counter by value
-    pub fn pc_increment(&mut self, val: usize) -> () {
-        self.pc = self.pc + val
+    pub fn pc_increment(&mut self, val: usize) {
+        self.pc += val
     }
 
     // Push value onto stack then increment program counter by 1
@@ -83,35 +234,370 @@ impl ExecutionContext {
         Ok(value)
     }
 
-    // Begin code execution
-    pub fn run(&mut self) -> Result<(), StatusCode> {
+    // Charge expansion gas for memory access to [offset, offset + size), returning both as usize.
+    // Offsets beyond addressable memory fail with OutOfGas before anything is allocated
+    pub fn memory_expand(&mut self, offset: U256BE, size: U256BE) -> Result<(usize, usize), StatusCode> {
+        let size = size.try_to_usize().ok_or(StatusCode::OutOfGas)?;
+        if size == 0 { return Ok((0, 0)); };
+        let offset = offset.try_to_usize().ok_or(StatusCode::OutOfGas)?;
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= MAX_MEMORY_SIZE)
+            .ok_or(StatusCode::OutOfGas)?;
+        let words = end.div_ceil(32);
+        let current = self.memory.words();
+        if words > current {
+            self.sub_gas(memory_gas(words) - memory_gas(current))?;
+            self.memory.expand(offset, size)?;
+        };
+        Ok((offset, size))
+    }
+
+    // Charge per-word gas for copying size bytes
+    pub fn copy_gas(&mut self, size: usize) -> Result<(), StatusCode> {
+        self.sub_gas(COPY_WORD_GAS * size.div_ceil(32))
+    }
+
+    // Check call depth and transfer message value to the frame's account
+    fn enter(&mut self) -> Result<(), StatusCode> {
+        if self.depth > CALL_DEPTH_LIMIT { return Err(StatusCode::CallDepthExceeded); };
+        // Transaction sender, recipient and coinbase (EIP-3651) start warm
+        if self.depth == 0 {
+            self.state.access_address(self.caller);
+            self.state.access_address(self.address);
+            if self.spec.is_enabled(SpecId::Shanghai) { self.state.access_address(self.block.coinbase); };
+            for address in self.precompiles.addresses() { self.state.access_address(*address); }
+        };
+        match self.kind {
+            CallKind::Call | CallKind::CallCode => self.state.transfer(self.caller, self.address, self.value),
+            CallKind::Create | CallKind::Create2 => {
+                // Creating over an account with code, nonce or storage fails and consumes all gas (EIP-684)
+                if let Some(acc) = self.state.account(self.address) {
+                    if acc.nonce != 0 || !acc.code().is_empty() || !acc.storage.is_empty() {
+                        return Err(StatusCode::Failure);
+                    };
+                };
+                self.state.mark_created(self.address);
+                // New contracts start with nonce 1 (EIP-161)
+                if self.spec.is_enabled(SpecId::SpuriousDragon) { self.state.set_nonce(self.address, 1); };
+                self.state.transfer(self.caller, self.address, self.value)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    // Validate returned runtime code and install it at the created address
+    fn deposit_code(&mut self) -> Result<(), StatusCode> {
+        // Code size limit (EIP-170)
+        if self.spec.is_enabled(SpecId::SpuriousDragon) && self.output.len() > MAX_CODE_SIZE {
+            return Err(StatusCode::ContractValidationFailure);
+        };
+        // Reject code starting with the 0xEF byte (EIP-3541)
+        if self.spec.is_enabled(SpecId::London) && self.output.first() == Some(&0xEF) {
+            return Err(StatusCode::ContractValidationFailure);
+        };
+        // Before Homestead, failing to pay for the code leaves the account with empty code
+        if self.sub_gas(CODE_DEPOSIT_GAS * self.output.len()).is_err() {
+            if self.spec.is_enabled(SpecId::Homestead) { return Err(StatusCode::OutOfGas); };
+            self.output.clear();
+        };
+        let code = std::mem::take(&mut self.output);
+        self.state.set_code(self.address, code);
+        Err(StatusCode::Completion)
+    }
+
+    // Begin code execution, reverting state changes on failure
+    pub fn run(&mut self) -> ExecutionResult {
+        let checkpoint = self.state.checkpoint();
+        let is_create = matches!(self.kind, CallKind::Create | CallKind::Create2);
+        let result = match self.enter().and_then(|_| self.execute()) {
+            Ok(_) | Err(StatusCode::Completion) if is_create => self.deposit_code(),
+            result => result,
+        };
+        // Accesses are collected before a revert undoes them, so failed transactions report what they touched
+        let access_list = match self.depth {
+            0 => {
+                let mut exclude = vec![self.caller, self.address, self.block.coinbase];
+                exclude.extend(self.precompiles.addresses());
+                self.state.access_list(&exclude)
+            },
+            _ => Vec::new(),
+        };
+        let reason = match result {
+            Ok(_) | Err(StatusCode::Completion) => {
+                self.state.commit(checkpoint);
+                HaltReason::Success
+            },
+            Err(StatusCode::Revert) => {
+                self.state.revert_to(checkpoint);
+                HaltReason::Revert
+            },
+            // Calls rejected before execution keep their remaining gas
+            Err(code @ (StatusCode::InsufficientBalance | StatusCode::CallDepthExceeded)) => {
+                self.state.revert_to(checkpoint);
+                HaltReason::Exceptional(code)
+            },
+            // Exceptional halts consume all gas and return no data
+            Err(code) => {
+                self.state.revert_to(checkpoint);
+                self.gas_limit = 0;
+                self.output.clear();
+                HaltReason::Exceptional(code)
+            },
+        };
+        let logs = match self.depth {
+            0 => {
+                let logs = self.state.take_logs();
+                if self.ends_transaction { self.state.end_transaction(); };
+                logs
+            },
+            _ => Vec::new(),
+        };
+        ExecutionResult {
+            reason,
+            gas_used: self.gas_start - self.gas_limit,
+            gas_refunded: self.refund.max(0) as usize,
+            output: std::mem::take(&mut self.output),
+            logs,
+            access_list,
+            created_address: match is_create && reason == HaltReason::Success {
+                true => Some(self.address),
+                false => None,
+            },
+        }
+    }
+
+    // Append log with n topics from the stack
+    pub fn log(&mut self, n: usize) -> Result<(), StatusCode> {
+        if self.is_static { return Err(StatusCode::StaticModeViolation); };
+        let offset = self.stack.pop()?;
+        let length = self.stack.pop()?;
+        let mut topics = Vec::with_capacity(n);
+        for _ in 0..n { topics.push(self.stack.pop()?); }
+        let (offset, length) = self.memory_expand(offset, length)?;
+        self.sub_gas(LOG_DATA_GAS * length)?;
+        let data = self.memory.load_range(offset, length)?;
+        self.state.log(Log { address: self.address, topics, data });
+        self.pc_increment(1);
+        Ok(())
+    }
+
+    // Send the frame's balance to the beneficiary and halt. The account is deleted at the end of the
+    // transaction, since Cancun only if it was created in the same transaction (EIP-6780)
+    pub fn selfdestruct(&mut self) -> Result<(), StatusCode> {
+        if self.is_static { return Err(StatusCode::StaticModeViolation); };
+        let beneficiary = self.stack.pop()?.to_u160();
+        let cold = self.state.access_address(beneficiary);
+        if cold && self.spec.is_enabled(SpecId::Berlin) { self.sub_gas(COLD_ACCOUNT_ACCESS_GAS)?; };
+        let balance = self.state.balance(self.address);
+        let new_account = match self.spec.is_enabled(SpecId::SpuriousDragon) {
+            true => balance != 0 && self.state.account(beneficiary).is_none_or(|acc| acc.is_empty()),
+            false => !self.state.exists(beneficiary),
+        };
+        if new_account && self.spec.is_enabled(SpecId::TangerineWhistle) { self.sub_gas(CALL_NEW_ACCOUNT_GAS)?; };
+        if !self.spec.is_enabled(SpecId::London) && !self.state.is_destructed(self.address) {
+            self.refund += SELFDESTRUCT_REFUND;
+        };
+
+        let destroy = !self.spec.is_enabled(SpecId::Cancun) || self.state.is_created(self.address);
+        if beneficiary != self.address {
+            self.state.transfer(self.address, beneficiary, balance)?;
+        } else if destroy {
+            // Sending the balance to the destroyed account itself burns it
+            self.state.set_balance(self.address, u256::ZERO);
+        };
+        if destroy { self.state.selfdestruct(self.address); };
+        self.stop();
+        Ok(())
+    }
+
+    // Execute CALL-family opcode in a child frame, pushing 1 on success and 0 on failure
+    pub fn call(&mut self, kind: CallKind) -> Result<(), StatusCode> {
+        let gas = self.stack.pop()?.to_u256();
+        let target = self.stack.pop()?.to_u160();
+        let value = match kind {
+            CallKind::Call | CallKind::CallCode => self.stack.pop()?.to_u256(),
+            _ => u256::ZERO,
+        };
+        let args_offset = self.stack.pop()?;
+        let args_size = self.stack.pop()?;
+        let ret_offset = self.stack.pop()?;
+        let ret_size = self.stack.pop()?;
+        if kind == CallKind::Call && self.is_static && value != 0 { return Err(StatusCode::StaticModeViolation); };
+        let (args_offset, args_size) = self.memory_expand(args_offset, args_size)?;
+        let (ret_offset, ret_size) = self.memory_expand(ret_offset, ret_size)?;
+        self.account_access_gas(target)?;
+        if let Some(delegate) = self.state.delegation(target) { self.account_access_gas(delegate)?; };
+
+        let mut cost = 0;
+        if value != 0 { cost += CALL_VALUE_GAS; };
+        // New account surcharge: for value transfers to empty accounts since EIP-161, any missing account before
+        if kind == CallKind::Call {
+            let new_account = match self.spec.is_enabled(SpecId::SpuriousDragon) {
+                true => value != 0 && self.state.account(target).is_none_or(|acc| acc.is_empty()),
+                false => !self.state.exists(target),
+            };
+            if new_account { cost += CALL_NEW_ACCOUNT_GAS; };
+        };
+        self.sub_gas(cost)?;
+        // Forward at most all but one 64th of remaining gas (EIP-150); earlier forks require the full amount
+        let gas = match self.spec.is_enabled(SpecId::TangerineWhistle) {
+            true => {
+                let available = self.gas_limit - self.gas_limit / 64;
+                if gas > u256::from(available as u64) { available } else { gas.as_usize() }
+            },
+            false => {
+                if gas > u256::from(self.gas_limit as u64) { return Err(StatusCode::OutOfGas); };
+                gas.as_usize()
+            },
+        };
+        self.sub_gas(gas)?;
+        let stipend = if value != 0 { CALL_STIPEND } else { 0 };
+        self.returndata.clear();
+
+        let message = Message {
+            kind,
+            caller: if kind == CallKind::DelegateCall { self.caller } else { self.address },
+            address: match kind {
+                CallKind::Call | CallKind::StaticCall => target,
+                _ => self.address,
+            },
+            code_address: target,
+            value: if kind == CallKind::DelegateCall { self.value } else { value },
+            calldata: self.memory.load_range(args_offset, args_size)?,
+            gas: gas + stipend,
+            depth: self.depth + 1,
+            is_static: self.is_static || kind == CallKind::StaticCall,
+        };
+        let mut child = ExecutionContext::init(message, Stack::init(), Memory::init(), &mut *self.state, self.spec);
+        child.block = self.block.clone();
+        child.env = self.env.clone();
+        child.precompiles = self.precompiles.clone();
+        child.trace = self.trace;
+        let result = child.run();
+        self.gas_limit += child.gas_limit;
+        let success = result.is_success();
+        if success { self.refund += child.refund; };
+        self.returndata = result.output;
+        let copied = ret_size.min(self.returndata.len());
+        self.memory.store_slice(ret_offset, &self.returndata[..copied])?;
+        self.stack_step_push(U256BE::from_u8(success as u8))
+    }
+
+    // Execute CREATE or CREATE2 in a child frame, pushing the new address on success and 0 on failure
+    pub fn create(&mut self, kind: CallKind) -> Result<(), StatusCode> {
+        if self.is_static { return Err(StatusCode::StaticModeViolation); };
+        let value = self.stack.pop()?.to_u256();
+        let offset = self.stack.pop()?;
+        let size = self.stack.pop()?;
+        let salt = match kind {
+            CallKind::Create2 => self.stack.pop()?,
+            _ => U256BE::zero(),
+        };
+        // Initcode size limit and word cost (EIP-3860)
+        let limit_initcode = self.spec.is_enabled(SpecId::Shanghai);
+        if limit_initcode && size.saturating_to_usize() > MAX_INITCODE_SIZE { return Err(StatusCode::OutOfGas); };
+        let (offset, size) = self.memory_expand(offset, size)?;
+        let words = size.div_ceil(32);
+        let mut cost = 0;
+        if limit_initcode { cost += INITCODE_WORD_GAS * words; };
+        if kind == CallKind::Create2 { cost += SHA3_WORD_GAS * words; };
+        self.sub_gas(cost)?;
+        let initcode = self.memory.load_range(offset, size)?;
+        self.returndata.clear();
+
+        let nonce = self.state.nonce(self.address);
+        if self.depth >= CALL_DEPTH_LIMIT || self.state.balance(self.address) < value || nonce == u64::MAX as usize {
+            return self.stack_step_push(U256BE::zero());
+        };
+        self.state.set_nonce(self.address, nonce + 1);
+        let address = match kind {
+            CallKind::Create2 => create2_address(self.address, salt, &initcode),
+            _ => create_address(self.address, nonce),
+        };
+        self.state.access_address(address);
+        // Forward all but one 64th of remaining gas (EIP-150); earlier forks forward everything
+        let gas = match self.spec.is_enabled(SpecId::TangerineWhistle) {
+            true => self.gas_limit - self.gas_limit / 64,
+            false => self.gas_limit,
+        };
+        self.sub_gas(gas)?;
+
+        let message = Message {
+            kind,
+            caller: self.address,
+            address,
+            code_address: address,
+            value,
+            calldata: initcode,
+            gas,
+            depth: self.depth + 1,
+            is_static: false,
+        };
+        let mut child = ExecutionContext::init(message, Stack::init(), Memory::init(), &mut *self.state, self.spec);
+        child.block = self.block.clone();
+        child.env = self.env.clone();
+        child.precompiles = self.precompiles.clone();
+        child.trace = self.trace;
+        let result = child.run();
+        self.gas_limit += child.gas_limit;
+        match result.created_address {
+            Some(address) => {
+                self.refund += child.refund;
+                self.stack_step_push(address.to_u256_be())
+            },
+            None => {
+                self.returndata = result.output;
+                self.stack_step_push(U256BE::zero())
+            },
+        }
+    }
+
+    // Run the precompile at the code address, or interpret code
+    fn execute(&mut self) -> Result<(), StatusCode> {
+        match self.precompiles.get(self.code_address) {
+            Some(precompile) if !matches!(self.kind, CallKind::Create | CallKind::Create2) => self.run_precompile(precompile),
+            _ => self.interpret(),
+        }
+    }
+
+    // Charge precompile gas and run it on calldata
+    fn run_precompile(&mut self, precompile: Rc<dyn Precompile>) -> Result<(), StatusCode> {
+        self.sub_gas(precompile.gas(&self.calldata))?;
+        self.output = precompile.run(&self.calldata)?;
+        Ok(())
+    }
+
+    // Execute opcodes until halted
+    fn interpret(&mut self) -> Result<(), StatusCode> {
         while !self.stopped {
             let opcode: u8 = self.read_code(0)?;
-            println!(
-                "[ Opcode: {} | PC: {} | Gas: {} ]",
-                opcode, self.pc, self.gas_limit
-            );
-            match self.exec(opcode) {
-                Err(e) => return Err(e),
-                Ok(_) => (),
+            if self.trace { println!("[ Opcode: {} | PC: {} | Gas: {} ]", opcode, self.pc, self.gas_limit); };
+            if !self.spec.is_enabled(introduced_in(opcode)) { return Err(StatusCode::UndefinedInstruction); };
+            self.sub_gas(gas_fetch(opcode, self.spec))?;
+            // Calls are dispatched outside of exec so its large frame is not kept on the native stack per call depth
+            match opcode {
+                CALL => self.call(CallKind::Call)?,
+                CALLCODE => self.call(CallKind::CallCode)?,
+                DELEGATECALL => self.call(CallKind::DelegateCall)?,
+                STATICCALL => self.call(CallKind::StaticCall)?,
+                CREATE => self.create(CallKind::Create)?,
+                CREATE2 => self.create(CallKind::Create2)?,
+                _ => self.exec(opcode)?,
             };
-            self.sub_gas(gas_fetch(opcode))?;
-            println!(
-                "Stack: {:?}\nMemory: {:?}",
-                self.stack.peek_full(),
-                self.memory.load_full()
-            );
+            if self.trace { println!("Stack: {:?}\nMemory: {:?}", self.stack.peek_full(), self.memory.load_full()); };
         }
         Ok(())
     }
 
-    // Execute opcode
-    pub fn exec(&mut self, opcode: u8) -> Result<(), StatusCode> {
+    // Execute opcode other than CALL*/CREATE*, which interpret dispatches itself
+    fn exec(&mut self, opcode: u8) -> Result<(), StatusCode> {
         // Push n values onto stack
         macro_rules! pushn {
             ( $n:expr ) => {{
-                let slice = &self.code[self.pc + 1..=self.pc + $n];
-                let ret = U256BE::from_slice(slice);
+                // Immediates truncated by the end of code are zero-padded on the right
+                let slice = padded_slice(&self.code, self.pc + 1, $n);
+                let ret = U256BE::from_slice(&slice);
                 self.stack.push(ret)?;
                 self.pc_increment($n + 1);
                 Ok(())
@@ -180,6 +666,23 @@ impl ExecutionContext {
             PUSH14 => pushn!(14),
             PUSH15 => pushn!(15),
             PUSH16 => pushn!(16),
+            PUSH17 => pushn!(17),
+            PUSH18 => pushn!(18),
+            PUSH19 => pushn!(19),
+            PUSH20 => pushn!(20),
+            PUSH21 => pushn!(21),
+            PUSH22 => pushn!(22),
+            PUSH23 => pushn!(23),
+            PUSH24 => pushn!(24),
+            PUSH25 => pushn!(25),
+            PUSH26 => pushn!(26),
+            PUSH27 => pushn!(27),
+            PUSH28 => pushn!(28),
+            PUSH29 => pushn!(29),
+            PUSH30 => pushn!(30),
+            PUSH31 => pushn!(31),
+            PUSH32 => pushn!(32),
+            PUSH0 => self.stack_step_push(U256BE::zero()),
             POP => { self.stack.pop()?; self.pc_increment(1); Ok(()) },
             DUP1 => dupn!(1),
             DUP2 => dupn!(2),
@@ -219,9 +722,10 @@ impl ExecutionContext {
             DIV => term_eval!(overflowing_div),
             MOD => term_eval!(overflowing_rem),
             EXP => {
-                let val1 = self.stack.pop()?.to_u256();
-                let val2 = self.stack.pop()?.to_u32();
-                self.stack_step_push(U256BE::from_u256(val1.overflowing_pow(val2).0))
+                let base = self.stack.pop()?;
+                let exponent = self.stack.pop()?;
+                self.sub_gas(exp_byte_gas(self.spec) * exponent.byte_len())?;
+                self.stack_step_push(base.exp(exponent))
             },
             SDIV => signed_term_eval!(overflowing_div),
             SMOD => signed_term_eval!(overflowing_rem),
@@ -248,19 +752,19 @@ impl ExecutionContext {
                 self.stack_step_push(val1.slt(val2))
             },
             SHL => {
-                let val1 = self.stack.pop()?;
-                let val2 = self.stack.pop()?;
-                self.stack_step_push(val1.shl(val2))
+                let shift = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                self.stack_step_push(value.shl(shift))
             },
             SHR => {
-                let val1 = self.stack.pop()?;
-                let val2 = self.stack.pop()?;
-                self.stack_step_push(val1.shr(val2))
+                let shift = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                self.stack_step_push(value.shr(shift))
             },
             SAR => {
-                let val1 = self.stack.pop()?;
-                let val2 = self.stack.pop()?;
-                self.stack_step_push(val1.sar(val2))
+                let shift = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                self.stack_step_push(value.sar(shift))
             },
             EQ => {
                 let val1 = self.stack.pop()?;
@@ -291,82 +795,541 @@ impl ExecutionContext {
                 self.stack_step_push(val.not())
             },
             BYTE => {
-                let nth = self.stack.pop()?.to_usize();
+                let nth = self.stack.pop()?.saturating_to_usize();
                 let val = self.stack.pop()?.as_slice();
                 if nth >= val.len() { return self.stack_step_push(U256BE::zero()); };
                 self.stack_step_push(U256BE::from_u8(val[nth]))
             },
             MLOAD => {
-                let offset = self.stack.pop()?.to_usize();
+                let offset = self.stack.pop()?;
+                let (offset, _) = self.memory_expand(offset, U256BE::from_u8(32))?;
                 let loaded = self.memory.load(offset)?;
                 self.stack_step_push(U256BE::from_slice(loaded.as_slice()))
             },
             MSTORE => {
-                let offset = self.stack.pop()?.to_usize();
+                let offset = self.stack.pop()?;
                 let value = self.stack.pop()?;
+                let (offset, _) = self.memory_expand(offset, U256BE::from_u8(32))?;
                 self.memory.store(offset, value)?;
                 self.pc_increment(1);
                 Ok(())
             },
             MSTORE8 => {
-                let offset = self.stack.pop()?.to_usize();
+                let offset = self.stack.pop()?;
                 let value = self.stack.pop()?;
-                self.memory.store(offset, value.and(U256BE::from_u8(0xFFu8)))?;
+                let (offset, _) = self.memory_expand(offset, U256BE::from_u8(1))?;
+                self.memory.store_slice(offset, &value.as_slice()[31..])?;
                 self.pc_increment(1);
                 Ok(())
             },
             SHA3 => {
-                let offset = self.stack.pop()?.to_usize();
-                let length = self.stack.pop()?.to_usize();
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+                let (offset, length) = self.memory_expand(offset, length)?;
+                self.sub_gas(SHA3_WORD_GAS * length.div_ceil(32))?;
                 let value = self.memory.load_range(offset, length)?;
-                let mut hasher = Keccak256::default();
-                hasher.update(value.as_slice());
-                let ret = U256BE::from_slice(hasher.finalize().to_vec().as_slice());
-                self.stack_step_push(ret)
+                self.stack_step_push(keccak256(value.as_slice()))
+            },
+            SLOAD => {
+                let key = self.stack.pop()?;
+                self.storage_access_gas(key, COLD_SLOAD_GAS - WARM_STORAGE_READ_GAS)?;
+                self.stack_step_push(self.state.sload(self.address, key))
+            },
+            SSTORE => {
+                let key = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                self.sstore_gas(key, value)?;
+                self.state.sstore(self.address, key, value);
+                self.pc_increment(1);
+                Ok(())
+            },
+            TLOAD => {
+                let key = self.stack.pop()?;
+                self.stack_step_push(self.state.tload(self.address, key))
+            },
+            TSTORE => {
+                if self.is_static { return Err(StatusCode::StaticModeViolation); };
+                let key = self.stack.pop()?;
+                let value = self.stack.pop()?;
+                self.state.tstore(self.address, key, value);
+                self.pc_increment(1);
+                Ok(())
+            },
+            MCOPY => {
+                let dest = self.stack.pop()?;
+                let src = self.stack.pop()?;
+                let size = self.stack.pop()?;
+                let (src, size) = self.memory_expand(src, size)?;
+                let (dest, size) = self.memory_expand(dest, U256BE::from_usize(size))?;
+                self.copy_gas(size)?;
+                self.memory.copy_within(dest, src, size)?;
+                self.pc_increment(1);
+                Ok(())
             },
             MSIZE => {
                 let memlen = self.memory.len();
                 self.stack_step_push(U256BE::from_usize(memlen))
             },
             CALLDATALOAD => {
-                let offset = self.stack.pop()?.to_usize();
-                let loaded = self.calldata_load(offset)?;
-                self.stack_step_push(U256BE::from_slice(loaded.as_slice()))
+                let offset = self.stack.pop()?.saturating_to_usize();
+                self.stack_step_push(self.calldata_load(offset))
             },
             CALLDATASIZE => {
                 self.stack_step_push(U256BE::from_usize(self.calldata.len()))
             },
-            JUMPDEST => {
-                let dest = self.stack.pop()?.to_usize();
-                if dest >= self.code.len() { return Err(StatusCode::BadJumpDest); };
+            CALLDATACOPY => {
+                let dest = self.stack.pop()?;
+                let offset = self.stack.pop()?.saturating_to_usize();
+                let size = self.stack.pop()?;
+                let (dest, size) = self.memory_expand(dest, size)?;
+                self.copy_gas(size)?;
+                self.memory.store_padded(dest, &self.calldata, offset, size)?;
                 self.pc_increment(1);
                 Ok(())
             },
+            JUMPDEST => { self.pc_increment(1); Ok(()) },
             JUMPI => {
                 let dest = self.stack.pop()?;
                 let cond = self.stack.pop()?;
                 if cond.is_zero() { self.pc_increment(1); Ok(()) }
-                else { self.pc_jump(dest.to_usize()) }
+                else { self.pc_jump(dest) }
+            },
+            JUMP => { let dest = self.stack.pop()?; self.pc_jump(dest) },
+            ADDRESS => self.stack_step_push(self.address.to_u256_be()),
+            ORIGIN => self.stack_step_push(self.env.origin.to_u256_be()),
+            CALLER => self.stack_step_push(self.caller.to_u256_be()),
+            CALLVALUE => self.stack_step_push(U256BE::from_u256(self.value)),
+            GASPRICE => self.stack_step_push(U256BE::from_usize(self.env.gasprice)),
+            BLOBHASH => {
+                let index = self.stack.pop()?.try_to_usize();
+                let hash = index.and_then(|i| self.env.blob_hashes.get(i)).copied().unwrap_or_default();
+                self.stack_step_push(hash)
+            },
+            BALANCE => {
+                let address = self.stack.pop()?.to_u160();
+                self.account_access_gas(address)?;
+                self.stack_step_push(U256BE::from_u256(self.state.balance(address)))
+            },
+            SELFBALANCE => self.stack_step_push(U256BE::from_u256(self.state.balance(self.address))),
+            CODESIZE => self.stack_step_push(U256BE::from_usize(self.code.len())),
+            CODECOPY => {
+                let dest = self.stack.pop()?;
+                let offset = self.stack.pop()?.saturating_to_usize();
+                let size = self.stack.pop()?;
+                let (dest, size) = self.memory_expand(dest, size)?;
+                self.copy_gas(size)?;
+                self.memory.store_padded(dest, &self.code, offset, size)?;
+                self.pc_increment(1);
+                Ok(())
+            },
+            EXTCODESIZE => {
+                let address = self.stack.pop()?.to_u160();
+                self.account_access_gas(address)?;
+                self.stack_step_push(U256BE::from_usize(self.state.code(address).len()))
+            },
+            EXTCODECOPY => {
+                let address = self.stack.pop()?.to_u160();
+                let dest = self.stack.pop()?;
+                let offset = self.stack.pop()?.saturating_to_usize();
+                let size = self.stack.pop()?;
+                let (dest, size) = self.memory_expand(dest, size)?;
+                self.copy_gas(size)?;
+                self.account_access_gas(address)?;
+                self.memory.store_padded(dest, self.state.code(address), offset, size)?;
+                self.pc_increment(1);
+                Ok(())
+            },
+            EXTCODEHASH => {
+                let address = self.stack.pop()?.to_u160();
+                self.account_access_gas(address)?;
+                self.stack_step_push(self.state.codehash(address))
             },
-            JUMP => { let dest = self.stack.pop()?; self.pc_jump(dest.to_usize()) },
             PC => self.stack_step_push(U256BE::from_usize(self.pc)),
             GAS => self.stack_step_push(U256BE::from_usize(self.gas_limit)),
-            GASLIMIT => self.stack_step_push(U256BE::from_usize(self.block.gaslimit)),
-            BASEFEE => self.stack_step_push(U256BE::from_usize(self.block.basegas)),
+            GASLIMIT => self.stack_step_push(U256BE::from_u256(u256::from(self.block.gaslimit))),
+            BASEFEE => self.stack_step_push(U256BE::from_u256(self.block.basefee)),
+            BLOBBASEFEE => self.stack_step_push(U256BE::from_usize(self.block.blob_basefee(self.spec))),
+            CHAINID => self.stack_step_push(U256BE::from_u256(u256::from(self.env.chain_id))),
+            BLOCKHASH => {
+                let number = self.stack.pop()?.to_u256();
+                self.stack_step_push(self.block.block_hash(number))
+            },
             COINBASE => self.stack_step_push(self.block.coinbase.to_u256_be()),
-            TIMESTAMP => self.stack_step_push(self.block.timestamp),
-            NUMBER => self.stack_step_push(U256BE::from_usize(self.block.blocknumber)),
-            DIFFICULTY => self.stack_step_push(U256BE::from_usize(self.block.difficulty)),
+            TIMESTAMP => self.stack_step_push(U256BE::from_u256(u256::from(self.block.timestamp))),
+            NUMBER => self.stack_step_push(U256BE::from_u256(u256::from(self.block.number))),
+            // PREVRANDAO replaces DIFFICULTY after the Merge (EIP-4399)
+            DIFFICULTY => match self.spec.is_enabled(SpecId::Paris) {
+                true => self.stack_step_push(self.block.prevrandao),
+                false => self.stack_step_push(U256BE::from_u256(self.block.difficulty)),
+            },
             STOP => { self.stop(); Err(StatusCode::Completion) },
             RETURN => {
-                let offset = self.stack.pop()?.to_usize();
-                let length = self.stack.pop()?.to_usize();
-                self.returndata = self.memory.load_range(offset, length)?;
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+                let (offset, length) = self.memory_expand(offset, length)?;
+                self.output = self.memory.load_range(offset, length)?;
                 self.stop();
-                println!("Return Data: {:?}", self.returndata);
                 Err(StatusCode::Completion)
             },
+            RETURNDATASIZE => self.stack_step_push(U256BE::from_usize(self.returndata.len())),
+            RETURNDATACOPY => {
+                let dest = self.stack.pop()?;
+                let offset = self.stack.pop()?.saturating_to_usize();
+                let size = self.stack.pop()?;
+                // Reading past the end of the return data buffer is a hard failure (EIP-211)
+                if offset.checked_add(size.saturating_to_usize()).is_none_or(|end| end > self.returndata.len()) {
+                    return Err(StatusCode::InvalidMemoryAccess);
+                };
+                let (dest, size) = self.memory_expand(dest, size)?;
+                self.copy_gas(size)?;
+                self.memory.store_slice(dest, &self.returndata[offset..offset + size])?;
+                self.pc_increment(1);
+                Ok(())
+            },
+            LOG0 => self.log(0),
+            LOG1 => self.log(1),
+            LOG2 => self.log(2),
+            LOG3 => self.log(3),
+            LOG4 => self.log(4),
+            REVERT => {
+                let offset = self.stack.pop()?;
+                let length = self.stack.pop()?;
+                let (offset, length) = self.memory_expand(offset, length)?;
+                self.output = self.memory.load_range(offset, length)?;
+                self.stop();
+                Err(StatusCode::Revert)
+            },
+            INVALID => Err(StatusCode::InvalidInstruction),
+            SELFDESTRUCT => self.selfdestruct(),
             _ => Err(StatusCode::UndefinedInstruction),
         }
     }
 }
+
+// Address of contract created by sender with nonce: keccak256(rlp([sender, nonce]))[12..]
+pub fn create_address(sender: U160, nonce: usize) -> U160 {
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce_bytes.iter().take_while(|b| **b == 0).count()..];
+    let mut rlp = vec![0u8, 0x80 + 20];
+    rlp.extend_from_slice(&sender.as_slice());
+    match nonce_bytes {
+        [b] if *b < 0x80 => rlp.push(*b),
+        _ => {
+            rlp.push(0x80 + nonce_bytes.len() as u8);
+            rlp.extend_from_slice(nonce_bytes);
+        },
+    };
+    rlp[0] = 0xC0 + (rlp.len() - 1) as u8;
+    keccak256(&rlp).to_u160()
+}
+
+// Address of contract created by CREATE2: keccak256(0xff ++ sender ++ salt ++ keccak256(initcode))[12..]
+pub fn create2_address(sender: U160, salt: U256BE, initcode: &[u8]) -> U160 {
+    let mut preimage = Vec::with_capacity(85);
+    preimage.push(0xFF);
+    preimage.extend_from_slice(&sender.as_slice());
+    preimage.extend_from_slice(&salt.as_slice());
+    preimage.extend_from_slice(&keccak256(initcode).as_slice());
+    keccak256(&preimage).to_u160()
+}
+
+// Return size bytes of data from offset, zero-padded past the end of data
+pub fn padded_slice(data: &[u8], offset: usize, size: usize) -> Vec<u8> {
+    let mut ret = vec![0u8; size];
+    if offset < data.len() {
+        let end = data.len().min(offset.saturating_add(size));
+        ret[..end - offset].clone_from_slice(&data[offset..end]);
+    };
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::state::{AccessListItem, Account};
+
+    fn addr(byte: u8) -> U160 {
+        U160::from_slice(&[byte])
+    }
+
+    // Install code at address
+    fn deploy(state: &mut WorldState, address: U160, balance: u64, code: &str) {
+        state.insert_account(address, Account::init(1, u256::from(balance), hex::decode(code).unwrap()));
+    }
+
+    // Message from 0x01 to 0x10 carrying value
+    fn message(value: u64) -> Message {
+        Message::call(addr(0x01), addr(0x10), u256::from(value), Vec::new(), 100_000)
+    }
+
+    // Run message with the Prague rules
+    fn run_message(state: &mut WorldState, message: Message) -> ExecutionResult {
+        ExecutionContext::init(message, Stack::init(), Memory::init(), state, SpecId::Prague).run()
+    }
+
+    // Run creation of a contract at 0x30 by 0x01 with initcode
+    fn run_create(state: &mut WorldState, initcode: &str, gas: usize, spec: SpecId) -> ExecutionResult {
+        let message = Message {
+            kind: CallKind::Create,
+            address: addr(0x30),
+            code_address: addr(0x30),
+            ..Message::call(addr(0x01), addr(0x30), u256::ZERO, hex::decode(initcode).unwrap(), gas)
+        };
+        ExecutionContext::init(message, Stack::init(), Memory::init(), state, spec).run()
+    }
+
+    // Run code deployed at 0x10 in a call from 0x01 and return the result
+    fn run_code(state: &mut WorldState, code: &str, block: Block) -> ExecutionResult {
+        deploy(state, addr(0x10), 0, code);
+        let message = Message::call(addr(0x01), addr(0x10), u256::ZERO, Vec::new(), 1_000_000);
+        let mut context = ExecutionContext::init(message, Stack::init(), Memory::init(), state, SpecId::Prague);
+        context.set_block(block);
+        context.run()
+    }
+
+    #[test]
+    fn memory_expansion_cost_is_quadratic() {
+        let mut state = WorldState::init();
+        let mut context = ExecutionContext::init(message(0), Stack::init(), Memory::init(), &mut state, SpecId::Prague);
+        let mut charged = |offset: usize, size: usize| {
+            let before = context.gas_limit;
+            context.memory_expand(U256BE::from_usize(offset), U256BE::from_usize(size)).unwrap();
+            before - context.gas_limit
+        };
+        // 3 per word plus words^2 / 512: 22 words cost 66, the 23rd adds 3 + 1
+        assert_eq!(charged(31, 1), 3);
+        assert_eq!(charged(0, 22 * 32), 63);
+        assert_eq!(charged(22 * 32, 1), 4);
+        // Already expanded memory and empty ranges are free
+        assert_eq!(charged(0, 23 * 32), 0);
+        assert_eq!(charged(usize::MAX, 0), 0);
+        assert_eq!(charged(0, 1024 * 32), 3 * 1024 + 1024 * 1024 / 512 - 70);
+    }
+
+    #[test]
+    fn absurd_memory_offset_is_out_of_gas_without_allocating() {
+        let mut state = WorldState::init();
+        let mut context = ExecutionContext::init(message(0), Stack::init(), Memory::init(), &mut state, SpecId::Prague);
+        let one = U256BE::from_u8(1);
+        let max = U256BE::from_u256(u256::MAX);
+        assert_eq!(context.memory_expand(max, one), Err(StatusCode::OutOfGas));
+        assert_eq!(context.memory_expand(one, max), Err(StatusCode::OutOfGas));
+        assert_eq!(context.memory_expand(U256BE::from_usize(MAX_MEMORY_SIZE), one), Err(StatusCode::OutOfGas));
+        // Within the size limit but far beyond the gas available
+        assert_eq!(context.memory_expand(U256BE::from_usize(1 << 30), one), Err(StatusCode::OutOfGas));
+        assert_eq!(context.memory.words(), 0);
+    }
+
+    #[test]
+    fn call_forwards_all_but_one_64th() {
+        // 0x20: MSTORE(0, GAS) RETURN(0, 32)
+        let mut state = WorldState::init();
+        deploy(&mut state, addr(0x20), 0, "5a60005260206000f3");
+        // CALL(2^256-1, 0x20, 0, 0, 0, 0, 32) RETURN(0, 32)
+        let code = format!("6020600060006000600060207f{}f160206000f3", "ff".repeat(32));
+        let result = run_code(&mut state, &code, Block::default());
+        // 1_000_000 minus pushes (21), warm call (100), memory (3) and cold access (2500) leaves 997376,
+        // of which 997376 - 997376 / 64 is forwarded; the callee spends 2 on GAS
+        let remaining = 1_000_000 - 21 - 100 - 3 - 2500;
+        let forwarded = remaining - remaining / 64;
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::from_usize(forwarded - 2));
+    }
+
+    #[test]
+    fn call_beyond_depth_limit_fails_keeping_gas() {
+        // MSTORE(0, CALL(0xFFFF, 0x20, 0, 0, 0, 0, 0)) RETURN(0, 32)
+        let mut state = WorldState::init();
+        deploy(&mut state, addr(0x10), 0, "60006000600060006000602061fffff160005260206000f3");
+        let message = Message { depth: CALL_DEPTH_LIMIT, ..message(0) };
+        let result = run_message(&mut state, message);
+        assert!(result.is_success());
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::zero());
+        // Only the call's own costs are charged; the 0xFFFF gas offered to the callee is returned
+        assert!(result.gas_used < 0xFFFF);
+    }
+
+    #[test]
+    fn call_with_insufficient_balance_fails_keeping_gas() {
+        // MSTORE(0, CALL(0xFFFF, 0x20, 1, 0, 0, 0, 0)) RETURN(0, 32), from an account without balance
+        let code = "60006000600060006001602061fffff160005260206000f3";
+        let result = run_code(&mut WorldState::init(), code, Block::default());
+        assert!(result.is_success());
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::zero());
+        assert!(result.gas_used < 0xFFFF);
+    }
+
+    #[test]
+    fn value_call_in_static_context_is_violation() {
+        // CALL(0, 0x20, 1, 0, 0, 0, 0)
+        let code = "6000600060006000600160206000f1";
+        let mut state = WorldState::init();
+        deploy(&mut state, addr(0x10), 1, code);
+        let message = Message { is_static: true, ..message(0) };
+        let result = run_message(&mut state, message);
+        assert_eq!(result.reason, HaltReason::Exceptional(StatusCode::StaticModeViolation));
+        assert_eq!(result.gas_used, 100_000);
+
+        // MSTORE(0, STATICCALL(0xFFFF, 0x20, 0, 0, 0, 0)) RETURN(0, 32) with the value call at 0x20
+        deploy(&mut state, addr(0x20), 1, code);
+        let result = run_code(&mut state, "6000600060006000602061fffffa60005260206000f3", Block::default());
+        assert!(result.is_success());
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::zero());
+        assert_eq!(state.balance(addr(0x20)), 1);
+    }
+
+    #[test]
+    fn delegatecall_and_callcode_keep_frame_account() {
+        // 0x20: SSTORE(0, CALLER) SSTORE(1, CALLVALUE) SSTORE(2, ADDRESS)
+        let mut state = WorldState::init();
+        state.insert_account(addr(0x01), Account::init(0, u256::from(5u64), Vec::new()));
+        deploy(&mut state, addr(0x20), 0, "336000553460015530600255");
+        let slot = |state: &WorldState, key: u8| state.sload(addr(0x10), U256BE::from_u8(key));
+
+        // DELEGATECALL(GAS, 0x20, 0, 0, 0, 0) sees the caller and value of the delegating frame
+        deploy(&mut state, addr(0x10), 0, "600060006000600060205af4");
+        assert!(run_message(&mut state, message(5)).is_success());
+        assert_eq!(slot(&state, 0), addr(0x01).to_u256_be());
+        assert_eq!(slot(&state, 1), U256BE::from_u8(5));
+        assert_eq!(slot(&state, 2), addr(0x10).to_u256_be());
+        assert_eq!(state.sload(addr(0x20), U256BE::zero()), U256BE::zero());
+
+        // CALLCODE(GAS, 0x20, 3, 0, 0, 0, 0) is called by the frame's account with its own value
+        state.set_code(addr(0x10), hex::decode("6000600060006000600360205af2").unwrap());
+        assert!(run_message(&mut state, message(0)).is_success());
+        assert_eq!(slot(&state, 0), addr(0x10).to_u256_be());
+        assert_eq!(slot(&state, 1), U256BE::from_u8(3));
+        assert_eq!(slot(&state, 2), addr(0x10).to_u256_be());
+        assert_eq!(state.balance(addr(0x10)), 5);
+    }
+
+    #[test]
+    fn code_size_limit() {
+        // RETURN(0, 0x6000) deploys the largest code allowed (EIP-170), one byte more fails
+        let result = run_create(&mut WorldState::init(), "6160006000f3", 10_000_000, SpecId::Prague);
+        assert!(result.is_success());
+        let result = run_create(&mut WorldState::init(), "6160016000f3", 10_000_000, SpecId::Prague);
+        assert_eq!(result.reason, HaltReason::Exceptional(StatusCode::ContractValidationFailure));
+        assert_eq!(result.gas_used, 10_000_000);
+        let result = run_create(&mut WorldState::init(), "6160016000f3", 10_000_000, SpecId::Homestead);
+        assert!(result.is_success());
+    }
+
+    #[test]
+    fn code_starting_with_ef_rejected_since_london() {
+        // MSTORE8(0, 0xEF) RETURN(0, 1)
+        let mut state = WorldState::init();
+        let result = run_create(&mut state, "60ef60005360016000f3", 100_000, SpecId::London);
+        assert_eq!(result.reason, HaltReason::Exceptional(StatusCode::ContractValidationFailure));
+        assert!(!state.exists(addr(0x30)));
+        let result = run_create(&mut state, "60ef60005360016000f3", 100_000, SpecId::Berlin);
+        assert!(result.is_success());
+        assert_eq!(state.code(addr(0x30)), &[0xEF]);
+    }
+
+    #[test]
+    fn initcode_size_limit_and_word_gas() {
+        let gas_used = |code: &str, spec| {
+            let mut state = WorldState::init();
+            deploy(&mut state, addr(0x10), 0, code);
+            let message = Message::call(addr(0x01), addr(0x10), u256::ZERO, Vec::new(), 1_000_000);
+            let result = ExecutionContext::init(message, Stack::init(), Memory::init(), &mut state, spec).run();
+            (result.reason, result.gas_used)
+        };
+        // CREATE(0, 0, size) of zeroed initcode
+        let (reason, create_32) = gas_used("602060006000f0", SpecId::Shanghai);
+        assert_eq!(reason, HaltReason::Success);
+        let (_, create_64) = gas_used("604060006000f0", SpecId::Shanghai);
+        // One more word of memory (3) and of initcode (2)
+        assert_eq!(create_64 - create_32, 3 + INITCODE_WORD_GAS);
+        let (_, create_32) = gas_used("602060006000f0", SpecId::Paris);
+        let (_, create_64) = gas_used("604060006000f0", SpecId::Paris);
+        assert_eq!(create_64 - create_32, 3);
+
+        // CREATE(0, 0, 0xC001) exceeds the initcode limit (EIP-3860)
+        let (reason, gas) = gas_used("61c00160006000f0", SpecId::Shanghai);
+        assert_eq!((reason, gas), (HaltReason::Exceptional(StatusCode::OutOfGas), 1_000_000));
+        let (reason, _) = gas_used("61c00060006000f0", SpecId::Shanghai);
+        assert_eq!(reason, HaltReason::Success);
+    }
+
+    #[test]
+    fn create_collision_fails() {
+        // MSTORE(0, CREATE(0, 0, 0)) RETURN(0, 32)
+        let mut state = WorldState::init();
+        let collision = create_address(addr(0x10), 1);
+        state.insert_account(collision, Account::init(1, u256::ZERO, Vec::new()));
+        let result = run_code(&mut state, "600060006000f060005260206000f3", Block::default());
+        assert!(result.is_success());
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::zero());
+        // The failed creation consumed all gas forwarded to it
+        assert!(result.gas_used > 900_000);
+        assert_eq!(state.nonce(addr(0x10)), 2);
+        let result = run_create(&mut state, "00", 100_000, SpecId::Prague);
+        assert!(result.is_success());
+        let result = run_create(&mut state, "00", 100_000, SpecId::Prague);
+        assert_eq!(result.reason, HaltReason::Exceptional(StatusCode::Failure));
+    }
+
+    #[test]
+    fn unpaid_code_deposit_leaves_empty_code_before_homestead() {
+        // MSTORE8(0, 1) RETURN(0, 1) costs 18 gas, leaving too little for the 200 gas deposit
+        let mut state = WorldState::init();
+        let result = run_create(&mut state, "600160005360016000f3", 100, SpecId::Frontier);
+        assert!(result.is_success());
+        assert_eq!(result.created_address, Some(addr(0x30)));
+        assert!(state.exists(addr(0x30)));
+        assert!(state.code(addr(0x30)).is_empty());
+        let mut state = WorldState::init();
+        let result = run_create(&mut state, "600160005360016000f3", 100, SpecId::Homestead);
+        assert_eq!(result.reason, HaltReason::Exceptional(StatusCode::OutOfGas));
+        assert!(!state.exists(addr(0x30)));
+    }
+
+    #[test]
+    fn transient_storage_reverts_with_frame_and_clears_at_transaction_end() {
+        // 0x20: TSTORE(0, 2) REVERT(0, 0)
+        let mut state = WorldState::init();
+        deploy(&mut state, addr(0x20), 0, "600260005d60006000fd");
+        // TSTORE(0, 1) POP(DELEGATECALL(GAS, 0x20, 0, 0, 0, 0)) MSTORE(0, TLOAD(0)) RETURN(0, 32)
+        let code = "600160005d600060006000600060205af45060005c60005260206000f3";
+        let result = run_code(&mut state, code, Block::default());
+        assert!(result.is_success());
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::from_u8(1));
+        assert_eq!(state.tload(addr(0x10), U256BE::zero()), U256BE::zero());
+    }
+
+    #[test]
+    fn tstore_in_static_context_is_violation() {
+        // TSTORE(0, 1)
+        let mut state = WorldState::init();
+        deploy(&mut state, addr(0x10), 0, "600160005d");
+        let result = run_message(&mut state, Message { is_static: true, ..message(0) });
+        assert_eq!(result.reason, HaltReason::Exceptional(StatusCode::StaticModeViolation));
+
+        // MSTORE(0, STATICCALL(0xFFFF, 0x20, 0, 0, 0, 0)) RETURN(0, 32) with the TSTORE at 0x20
+        deploy(&mut state, addr(0x20), 0, "600160005d");
+        let result = run_code(&mut state, "6000600060006000602061fffffa60005260206000f3", Block::default());
+        assert!(result.is_success());
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::zero());
+    }
+
+    #[test]
+    fn access_list_of_reverted_run() {
+        // BALANCE(0x77) POP SLOAD(5) POP REVERT(0, 0)
+        let result = run_code(&mut WorldState::init(), "607731506005545060006000fd", Block::default());
+        assert_eq!(result.reason, HaltReason::Revert);
+        let expected = vec![
+            AccessListItem { address: U160::from_slice(&[0x10]), storage_keys: vec![U256BE::from_u8(5)] },
+            AccessListItem { address: U160::from_slice(&[0x77]), storage_keys: Vec::new() },
+        ];
+        assert_eq!(result.access_list, expected);
+    }
+
+    #[test]
+    fn chainid_follows_block() {
+        // MSTORE(0, CHAINID) RETURN(0, 32)
+        let block = Block { chain_id: 5, ..Block::default() };
+        let result = run_code(&mut WorldState::init(), "4660005260206000f3", block);
+        assert_eq!(U256BE::from_slice(&result.output), U256BE::from_u8(5));
+    }
+}
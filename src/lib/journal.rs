@@ -0,0 +1,45 @@
+use super::custom_type::{U160, U256BE};
+use super::state::{Account, Log};
+use ethnum::u256;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+// State change recorded with the value needed to undo it
+pub enum JournalEntry {
+    // Whole account inserted or created; None if the address had no account
+    AccountChange { address: U160, previous: Option<Account> },
+    BalanceChange { address: U160, previous: u256 },
+    NonceChange { address: U160, previous: usize },
+    CodeChange { address: U160, previous: Vec<u8> },
+    StorageChange { address: U160, key: U256BE, previous: U256BE },
+    TransientStorageChange { address: U160, key: U256BE, previous: U256BE },
+    LogAdded,
+    // Logs removed from the current transaction when it was finalized
+    LogsTaken { logs: Vec<Log> },
+    // Address or storage key first accessed in the current transaction (EIP-2929)
+    AddressWarmed { address: U160 },
+    StorageWarmed { address: U160, key: U256BE },
+    // Account created or scheduled for deletion in the current transaction
+    AccountCreated { address: U160 },
+    AccountDestructed { address: U160 },
+    // Per-transaction state discarded when a transaction ended
+    TransactionEnded(Box<EndedTransaction>),
+}
+
+#[derive(Debug, Clone, Default)]
+// Per-transaction state as it was when the transaction ended
+pub struct EndedTransaction {
+    pub(super) originals: HashMap<(U160, U256BE), U256BE>,
+    pub(super) transient: HashMap<(U160, U256BE), U256BE>,
+    pub(super) accessed_addresses: HashSet<U160>,
+    pub(super) accessed_storage: HashSet<(U160, U256BE)>,
+    pub(super) created: HashSet<U160>,
+    pub(super) destructed: HashSet<U160>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Journal position to commit or revert to
+pub struct Checkpoint {
+    pub(super) journal_len: usize,
+    pub(super) depth: usize,
+}
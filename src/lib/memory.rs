@@ -1,6 +1,9 @@
 use super::custom_type::U256BE;
 use super::statuscode::StatusCode;
 
+// Largest addressable memory size; expansion gas makes anything beyond unreachable
+pub const MAX_MEMORY_SIZE: usize = u32::MAX as usize;
+
 pub struct Memory {
     // Vector of unsigned 8-bit integers to represent EVM memory
     storage: Vec<u8>,
@@ -13,35 +16,58 @@ impl Memory {
         }
     }
 
+    // Grow memory in 32-byte words to cover [offset, offset + length)
+    pub fn expand(&mut self, offset: usize, length: usize) -> Result<(), StatusCode> {
+        if length == 0 { return Ok(()); };
+        let end = offset.checked_add(length).ok_or(StatusCode::OutOfMemory)?;
+        if end > MAX_MEMORY_SIZE { return Err(StatusCode::OutOfMemory); };
+        if end > self.storage.len() { self.storage.resize(end.div_ceil(32) * 32, 0); };
+        Ok(())
+    }
+
     // Load 32-byte word from memory at offset
     pub fn load(&mut self, offset: usize) -> Result<Vec<u8>, StatusCode> {
-        let len_original = self.storage.len();
-        if offset + 31 >= len_original {
-            self.storage.resize((offset + len_original | 31) + 1, 0);
-        };
-        let ret = self.storage[offset..offset + 32].to_vec();
-        self.storage.truncate(len_original);
-        Ok(ret)
+        self.expand(offset, 32)?;
+        Ok(self.storage[offset..offset + 32].to_vec())
     }
 
     // Load bytes from memory within range
     pub fn load_range(&mut self, offset: usize, length: usize) -> Result<Vec<u8>, StatusCode> {
-        let len_original = self.storage.len();
-        if offset + length >= len_original {
-            self.storage.resize((offset + length + len_original | 31) + 1, 0);
-        };
-        let ret = self.storage[offset..offset + length].to_vec();
-        self.storage.truncate(len_original);
-        Ok(ret)
+        if length == 0 { return Ok(Vec::new()); };
+        self.expand(offset, length)?;
+        Ok(self.storage[offset..offset + length].to_vec())
     }
 
     // Store 32-byte word in memory at offset
     pub fn store(&mut self, offset: usize, value: U256BE) -> Result<(), StatusCode> {
-        if offset >= self.storage.len() { self.storage.resize(offset + 32, 0); };
-        self.storage[offset..offset + 32].clone_from_slice(&value.as_slice());
-        if self.storage.len() % 32 != 0 {
-            self.storage.resize((self.storage.len() | 31) + 1, 0);
-        };
+        self.store_slice(offset, &value.as_slice())
+    }
+
+    // Store arbitrary-length byte slice in memory at offset
+    pub fn store_slice(&mut self, offset: usize, value: &[u8]) -> Result<(), StatusCode> {
+        if value.is_empty() { return Ok(()); };
+        self.expand(offset, value.len())?;
+        self.storage[offset..offset + value.len()].clone_from_slice(value);
+        Ok(())
+    }
+
+    // Store size bytes of source starting at offset in memory at dest, zero-filling past the end of source
+    pub fn store_padded(&mut self, dest: usize, source: &[u8], offset: usize, size: usize) -> Result<(), StatusCode> {
+        if size == 0 { return Ok(()); };
+        self.expand(dest, size)?;
+        let start = offset.min(source.len());
+        let copied = size.min(source.len() - start);
+        self.storage[dest..dest + copied].copy_from_slice(&source[start..start + copied]);
+        self.storage[dest + copied..dest + size].fill(0);
+        Ok(())
+    }
+
+    // Copy length bytes from src to dest within memory; overlapping ranges copy as if through a buffer
+    pub fn copy_within(&mut self, dest: usize, src: usize, length: usize) -> Result<(), StatusCode> {
+        if length == 0 { return Ok(()); };
+        self.expand(src, length)?;
+        self.expand(dest, length)?;
+        self.storage.copy_within(src..src + length, dest);
         Ok(())
     }
 
@@ -51,7 +77,56 @@ impl Memory {
     }
 
     // Return length of memory
-    pub fn len(&mut self) -> usize {
+    pub fn len(&self) -> usize {
         self.storage.len()
     }
+
+    // Return length of memory in 32-byte words
+    pub fn words(&self) -> usize {
+        self.storage.len() / 32
+    }
+
+    // Return whether memory is empty
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Memory holding bytes 0x00, 0x01, ..., 0x1F
+    fn counting() -> Memory {
+        let mut memory = Memory::init();
+        memory.store_slice(0, &(0..32).collect::<Vec<u8>>()).unwrap();
+        memory
+    }
+
+    #[test]
+    fn copy_within_overlapping_backwards() {
+        // EIP-5656: MCOPY(0, 1, 8)
+        let mut memory = counting();
+        memory.copy_within(0, 1, 8).unwrap();
+        assert_eq!(memory.load_range(0, 9).unwrap(), vec![1, 2, 3, 4, 5, 6, 7, 8, 8]);
+    }
+
+    #[test]
+    fn copy_within_overlapping_forwards() {
+        // EIP-5656: MCOPY(1, 0, 8)
+        let mut memory = counting();
+        memory.copy_within(1, 0, 8).unwrap();
+        assert_eq!(memory.load_range(0, 9).unwrap(), vec![0, 0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn copy_within_expands_memory() {
+        let mut memory = counting();
+        memory.copy_within(40, 30, 4).unwrap();
+        assert_eq!(memory.len(), 64);
+        assert_eq!(memory.load_range(40, 4).unwrap(), vec![30, 31, 0, 0]);
+        // Empty copies never expand
+        memory.copy_within(1000, 2000, 0).unwrap();
+        assert_eq!(memory.len(), 64);
+    }
 }
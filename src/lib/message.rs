@@ -0,0 +1,53 @@
+use super::custom_type::U160;
+use ethnum::u256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Kind of message call
+pub enum CallKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+    Create,
+    Create2,
+}
+
+#[derive(Debug, Clone)]
+// Message Object: input to an execution frame
+pub struct Message {
+    pub kind: CallKind,
+    // Sender as seen by the callee (CALLER)
+    pub caller: U160,
+    // Account whose storage and balance the frame operates on
+    pub address: U160,
+    // Account whose code the frame executes (new account for CREATE and CREATE2)
+    pub code_address: U160,
+    // Value transferred (CALL, CALLCODE) or inherited (DELEGATECALL)
+    pub value: u256,
+    // Call input, or init code for CREATE and CREATE2
+    pub calldata: Vec<u8>,
+    pub gas: usize,
+    pub depth: usize,
+    pub is_static: bool,
+}
+impl Message {
+    // Initialize top-level call message from caller to address
+    pub fn call(caller: U160, address: U160, value: u256, calldata: Vec<u8>, gas: usize) -> Self {
+        Message {
+            kind: CallKind::Call,
+            caller,
+            address,
+            code_address: address,
+            value,
+            calldata,
+            gas,
+            depth: 0,
+            is_static: false,
+        }
+    }
+
+    // Returns whether message runs init code
+    pub fn is_create(&self) -> bool {
+        matches!(self.kind, CallKind::Create | CallKind::Create2)
+    }
+}
@@ -1,7 +1,16 @@
+pub mod analysis;
 pub mod custom_type;
 pub mod execution;
+pub mod journal;
 pub mod memory;
+pub mod message;
 pub mod opcode;
+pub mod precompile;
+pub mod result;
+pub mod spec;
 pub mod stack;
 pub mod state;
+pub mod storage;
+pub mod transact;
+pub mod world_state;
 pub mod statuscode;
@@ -1,6 +1,8 @@
 // Will reorder by value. Sometime soon.
+use super::spec::SpecId;
 
 // Stack-oriented opcodes
+pub const PUSH0: u8 = 0x5F;
 pub const PUSH1: u8 = 0x60;
 pub const PUSH2: u8 = 0x61;
 pub const PUSH3: u8 = 0x62;
@@ -17,6 +19,22 @@ pub const PUSH13: u8 = 0x6C;
 pub const PUSH14: u8 = 0x6D;
 pub const PUSH15: u8 = 0x6E;
 pub const PUSH16: u8 = 0x6F;
+pub const PUSH17: u8 = 0x70;
+pub const PUSH18: u8 = 0x71;
+pub const PUSH19: u8 = 0x72;
+pub const PUSH20: u8 = 0x73;
+pub const PUSH21: u8 = 0x74;
+pub const PUSH22: u8 = 0x75;
+pub const PUSH23: u8 = 0x76;
+pub const PUSH24: u8 = 0x77;
+pub const PUSH25: u8 = 0x78;
+pub const PUSH26: u8 = 0x79;
+pub const PUSH27: u8 = 0x7A;
+pub const PUSH28: u8 = 0x7B;
+pub const PUSH29: u8 = 0x7C;
+pub const PUSH30: u8 = 0x7D;
+pub const PUSH31: u8 = 0x7E;
+pub const PUSH32: u8 = 0x7F;
 pub const POP: u8 = 0x50;
 pub const DUP1: u8 = 0x80;
 pub const DUP2: u8 = 0x81;
@@ -79,6 +97,12 @@ pub const MLOAD: u8 = 0x51;
 pub const MSTORE: u8 = 0x52;
 pub const MSTORE8: u8 = 0x53;
 pub const MSIZE: u8 = 0x59;
+pub const MCOPY: u8 = 0x5E;
+// Storage-oriented opcodes
+pub const SLOAD: u8 = 0x54;
+pub const SSTORE: u8 = 0x55;
+pub const TLOAD: u8 = 0x5C;
+pub const TSTORE: u8 = 0x5D;
 // Bytecode-oriented opcodes
 pub const JUMP: u8 = 0x56;
 pub const JUMPI: u8 = 0x57;
@@ -87,35 +111,234 @@ pub const JUMPDEST: u8 = 0x5B;
 pub const CALLDATALOAD: u8 = 0x35;
 pub const CALLDATASIZE: u8 = 0x36;
 pub const CALLDATACOPY: u8 = 0x37;
+// Transaction-oriented opcodes
+pub const ORIGIN: u8 = 0x32;
+pub const CALLER: u8 = 0x33;
+pub const CALLVALUE: u8 = 0x34;
+pub const GASPRICE: u8 = 0x3A;
+pub const BLOBHASH: u8 = 0x49;
+// Account-oriented opcodes
+pub const ADDRESS: u8 = 0x30;
+pub const BALANCE: u8 = 0x31;
+pub const CODESIZE: u8 = 0x38;
+pub const CODECOPY: u8 = 0x39;
+pub const EXTCODESIZE: u8 = 0x3B;
+pub const EXTCODECOPY: u8 = 0x3C;
+pub const EXTCODEHASH: u8 = 0x3F;
+pub const SELFBALANCE: u8 = 0x47;
 // State-oriented opcodes
 pub const COINBASE: u8 = 0x41;
 pub const TIMESTAMP: u8 = 0x42;
 pub const NUMBER: u8 = 0x43;
 pub const DIFFICULTY: u8 = 0x44;
+pub const PREVRANDAO: u8 = DIFFICULTY;
 pub const GASLIMIT: u8 = 0x45;
 pub const BASEFEE: u8 = 0x48;
+pub const BLOCKHASH: u8 = 0x40;
+pub const CHAINID: u8 = 0x46;
+pub const BLOBBASEFEE: u8 = 0x4A;
+// Call-oriented opcodes
+pub const CREATE: u8 = 0xF0;
+pub const CREATE2: u8 = 0xF5;
+pub const CALL: u8 = 0xF1;
+pub const CALLCODE: u8 = 0xF2;
+pub const DELEGATECALL: u8 = 0xF4;
+pub const STATICCALL: u8 = 0xFA;
+// Return data-oriented opcodes
+pub const RETURNDATASIZE: u8 = 0x3D;
+pub const RETURNDATACOPY: u8 = 0x3E;
+// Log-oriented opcodes
+pub const LOG0: u8 = 0xA0;
+pub const LOG1: u8 = 0xA1;
+pub const LOG2: u8 = 0xA2;
+pub const LOG3: u8 = 0xA3;
+pub const LOG4: u8 = 0xA4;
 // Other opcodes
 pub const RETURN: u8 = 0xF3;
+pub const REVERT: u8 = 0xFD;
+pub const INVALID: u8 = 0xFE;
+pub const SELFDESTRUCT: u8 = 0xFF;
 pub const STOP: u8 = 0x00;
 pub const PC: u8 = 0x58;
 pub const GAS: u8 = 0x5A;
 pub const SHA3: u8 = 0x20;
 
-// Gas fetcher
-pub fn gas_fetch(key: u8) -> usize {
+// Storage gas costs (EIP-2200, EIP-2929)
+pub const SSTORE_SET_GAS: usize = 20000;
+pub const SSTORE_RESET_GAS: usize = 5000;
+pub const SSTORE_SENTRY_GAS: usize = 2300;
+pub const WARM_STORAGE_READ_GAS: usize = 100;
+pub const COLD_SLOAD_GAS: usize = 2100;
+pub const COLD_ACCOUNT_ACCESS_GAS: usize = 2600;
+// Call gas costs
+pub const CALL_VALUE_GAS: usize = 9000;
+pub const CALL_NEW_ACCOUNT_GAS: usize = 25000;
+pub const CALL_STIPEND: usize = 2300;
+pub const CALL_DEPTH_LIMIT: usize = 1024;
+// Refund for destroying an account, removed by EIP-3529
+pub const SELFDESTRUCT_REFUND: isize = 24000;
+// Creation gas costs and limits (EIP-170, EIP-3860)
+pub const CREATE_GAS: usize = 32000;
+pub const CODE_DEPOSIT_GAS: usize = 200;
+pub const INITCODE_WORD_GAS: usize = 2;
+pub const MAX_CODE_SIZE: usize = 24576;
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+// Transaction intrinsic gas costs (EIP-2028, EIP-2930, EIP-7623)
+pub const TX_GAS: usize = 21000;
+pub const TX_CREATE_GAS: usize = 32000;
+pub const TX_DATA_ZERO_GAS: usize = 4;
+pub const ACCESS_LIST_ADDRESS_GAS: usize = 2400;
+pub const ACCESS_LIST_STORAGE_KEY_GAS: usize = 1900;
+pub const TOTAL_COST_FLOOR_PER_TOKEN: usize = 10;
+// Set-code authorization costs (EIP-7702)
+pub const PER_AUTH_BASE_COST: usize = 12500;
+pub const PER_EMPTY_ACCOUNT_COST: usize = 25000;
+// Blob gas parameters (EIP-4844, EIP-7691)
+pub const GAS_PER_BLOB: usize = 131072;
+pub const MIN_BLOB_BASE_FEE: usize = 1;
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+// Log gas costs
+pub const LOG_GAS: usize = 375;
+pub const LOG_TOPIC_GAS: usize = 375;
+pub const LOG_DATA_GAS: usize = 8;
+// Per-word cost of hashing
+pub const SHA3_WORD_GAS: usize = 6;
+// Per-word cost of copy operations
+pub const COPY_WORD_GAS: usize = 3;
+// Linear per-word memory cost; quadratic cost divisor
+pub const MEMORY_WORD_GAS: usize = 3;
+pub const MEMORY_QUAD_DIVISOR: usize = 512;
+
+// SLOAD cost, also the cost of an SSTORE that leaves the slot unchanged under net gas metering
+pub fn sload_gas(spec: SpecId) -> usize {
+    match spec {
+        s if s.is_enabled(SpecId::Berlin) => WARM_STORAGE_READ_GAS,
+        s if s.is_enabled(SpecId::Istanbul) => 800,
+        s if s.is_enabled(SpecId::TangerineWhistle) => 200,
+        _ => 50,
+    }
+}
+
+// SSTORE cost of modifying a non-zero slot
+pub fn sstore_reset_gas(spec: SpecId) -> usize {
+    match spec.is_enabled(SpecId::Berlin) {
+        true => SSTORE_RESET_GAS - COLD_SLOAD_GAS,
+        false => SSTORE_RESET_GAS,
+    }
+}
+
+// Refund for clearing a storage slot (reduced by EIP-3529)
+pub fn sstore_clears_schedule(spec: SpecId) -> isize {
+    match spec.is_enabled(SpecId::London) {
+        true => 4800,
+        false => 15000,
+    }
+}
+
+// Cost per byte of EXP exponent (raised by EIP-160)
+pub fn exp_byte_gas(spec: SpecId) -> usize {
+    match spec.is_enabled(SpecId::SpuriousDragon) {
+        true => 50,
+        false => 10,
+    }
+}
+
+// Cost per non-zero byte of transaction data (reduced by EIP-2028)
+pub fn tx_data_non_zero_gas(spec: SpecId) -> usize {
+    match spec.is_enabled(SpecId::Istanbul) {
+        true => 16,
+        false => 68,
+    }
+}
+
+// Maximum number of blobs per block (raised by EIP-7691)
+pub fn max_blobs_per_block(spec: SpecId) -> usize {
+    match spec.is_enabled(SpecId::Prague) {
+        true => 9,
+        false => 6,
+    }
+}
+
+// Divisor of excess blob gas in the blob base fee exponent (raised by EIP-7691)
+pub fn blob_base_fee_update_fraction(spec: SpecId) -> usize {
+    match spec.is_enabled(SpecId::Prague) {
+        true => 5007716,
+        false => 3338477,
+    }
+}
+
+// Total cost of memory of the given size in words: 3 * words + words^2 / 512
+pub fn memory_gas(words: usize) -> usize {
+    MEMORY_WORD_GAS * words + words * words / MEMORY_QUAD_DIVISOR
+}
+
+// Static base gas cost of opcode, charged before execution. Dynamic costs are charged by the
+// opcode itself before any side effect (memory expansion, copies, storage, calls, creation)
+pub fn gas_fetch(key: u8, spec: SpecId) -> usize {
     // Linter makes this mess. Must configure.
     match key {
         JUMPDEST => 1,
-        POP | PC | GAS | CALLDATASIZE | MSIZE | COINBASE | TIMESTAMP | NUMBER | DIFFICULTY | GASLIMIT | BASEFEE => 2,
+        POP | PC | GAS | CALLDATASIZE | MSIZE | COINBASE | TIMESTAMP | NUMBER | DIFFICULTY | GASLIMIT | BASEFEE
+        | ADDRESS | CODESIZE | RETURNDATASIZE | PUSH0 | ORIGIN | CALLER | CALLVALUE | GASPRICE | CHAINID
+        | BLOBBASEFEE => 2,
         PUSH1 | PUSH2 | PUSH3 | PUSH4 | PUSH5 | PUSH6 | PUSH7 | PUSH8 | PUSH9 | PUSH10 | PUSH11
-        | PUSH12 | PUSH13 | PUSH14 | PUSH15 | PUSH16 | DUP1 | DUP2 | DUP3 | DUP4 | DUP5 | DUP6
+        | PUSH12 | PUSH13 | PUSH14 | PUSH15 | PUSH16 | PUSH17 | PUSH18 | PUSH19 | PUSH20 | PUSH21
+        | PUSH22 | PUSH23 | PUSH24 | PUSH25 | PUSH26 | PUSH27 | PUSH28 | PUSH29 | PUSH30 | PUSH31
+        | PUSH32 | DUP1 | DUP2 | DUP3 | DUP4 | DUP5 | DUP6
         | DUP7 | DUP8 | DUP9 | DUP10 | DUP11 | DUP12 | DUP13 | DUP14 | DUP15 | DUP16 | SWAP1
         | SWAP2 | SWAP3 | SWAP4 | SWAP5 | SWAP6 | SWAP7 | SWAP8 | SWAP9 | SWAP10 | SWAP11 | XOR
         | SWAP12 | SWAP13 | SWAP14 | SWAP15 | SWAP16 | ADD | SUB | EQ | ISZERO | AND | OR | NOT
-        | GT | LT | SGT | SLT | SHL | SHR | SAR | CALLDATALOAD | BYTE => 3,
-        MUL | DIV | SDIV | MOD | SMOD => 5,
+        | GT | LT | SGT | SLT | SHL | SHR | SAR | CALLDATALOAD | CALLDATACOPY | BYTE | CODECOPY
+        | RETURNDATACOPY | MLOAD | MSTORE | MSTORE8 | MCOPY | BLOBHASH => 3,
+        MUL | DIV | SDIV | MOD | SMOD | SELFBALANCE => 5,
         JUMP | ADDMOD | MULMOD => 8,
-        JUMPI => 10,
+        JUMPI | EXP => 10,
+        BLOCKHASH => 20,
+        SHA3 => 30,
+        LOG0 | LOG1 | LOG2 | LOG3 | LOG4 => LOG_GAS + LOG_TOPIC_GAS * (key - LOG0) as usize,
+        // Account access costs: repriced by EIP-150 and EIP-1884, warm cost from EIP-2929
+        BALANCE => match spec {
+            s if s.is_enabled(SpecId::Berlin) => WARM_STORAGE_READ_GAS,
+            s if s.is_enabled(SpecId::Istanbul) => 700,
+            s if s.is_enabled(SpecId::TangerineWhistle) => 400,
+            _ => 20,
+        },
+        EXTCODESIZE | EXTCODECOPY => match spec {
+            s if s.is_enabled(SpecId::Berlin) => WARM_STORAGE_READ_GAS,
+            s if s.is_enabled(SpecId::TangerineWhistle) => 700,
+            _ => 20,
+        },
+        EXTCODEHASH => match spec {
+            s if s.is_enabled(SpecId::Berlin) => WARM_STORAGE_READ_GAS,
+            s if s.is_enabled(SpecId::Istanbul) => 700,
+            _ => 400,
+        },
+        CALL | CALLCODE | DELEGATECALL | STATICCALL => match spec {
+            s if s.is_enabled(SpecId::Berlin) => WARM_STORAGE_READ_GAS,
+            s if s.is_enabled(SpecId::TangerineWhistle) => 700,
+            _ => 40,
+        },
+        SLOAD => sload_gas(spec),
+        TLOAD | TSTORE => WARM_STORAGE_READ_GAS,
+        CREATE | CREATE2 => CREATE_GAS,
+        SELFDESTRUCT => match spec.is_enabled(SpecId::TangerineWhistle) {
+            true => 5000,
+            false => 0,
+        },
         _ => 0,
     }
 }
+
+// Fork that introduced opcode
+pub fn introduced_in(key: u8) -> SpecId {
+    match key {
+        DELEGATECALL => SpecId::Homestead,
+        REVERT | RETURNDATASIZE | RETURNDATACOPY | STATICCALL => SpecId::Byzantium,
+        SHL | SHR | SAR | CREATE2 | EXTCODEHASH => SpecId::Constantinople,
+        SELFBALANCE | CHAINID => SpecId::Istanbul,
+        BASEFEE => SpecId::London,
+        PUSH0 => SpecId::Shanghai,
+        TLOAD | TSTORE | MCOPY | BLOBHASH | BLOBBASEFEE => SpecId::Cancun,
+        _ => SpecId::Frontier,
+    }
+}
@@ -0,0 +1,303 @@
+use super::custom_type::{keccak256, U160};
+use super::spec::SpecId;
+use super::statuscode::StatusCode;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use ripemd::Ripemd160 as Ripemd160Hasher;
+use sha2::{Digest, Sha256 as Sha256Hasher};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Precompiled contract: native code run in place of EVM code at a fixed address
+pub trait Precompile {
+    // Gas cost of running the precompile on input, charged before it runs
+    fn gas(&self, input: &[u8]) -> usize;
+    // Run the precompile on input, returning its output. Errors consume all gas given to the call
+    fn run(&self, input: &[u8]) -> Result<Vec<u8>, StatusCode>;
+}
+
+#[derive(Clone, Default)]
+// Registry of precompiles by address, consulted by the CALL family before running account code
+pub struct Precompiles {
+    contracts: HashMap<U160, Rc<dyn Precompile>>,
+}
+impl Precompiles {
+    // Initialize empty registry
+    pub fn init() -> Self {
+        Precompiles {
+            contracts: HashMap::new(),
+        }
+    }
+
+    // Initialize registry with the standard precompiles active in fork
+    pub fn for_spec(spec: SpecId) -> Self {
+        let mut precompiles = Precompiles::init();
+        for (address, introduced, precompile) in standard() {
+            if spec.is_enabled(introduced) { precompiles.register(U160::from_slice(&[address]), precompile); };
+        }
+        precompiles
+    }
+
+    // Register precompile at address, replacing any precompile already there
+    pub fn register(&mut self, address: U160, precompile: Rc<dyn Precompile>) {
+        self.contracts.insert(address, precompile);
+    }
+
+    // Remove precompile at address
+    pub fn remove(&mut self, address: U160) {
+        self.contracts.remove(&address);
+    }
+
+    // Return precompile at address
+    pub fn get(&self, address: U160) -> Option<Rc<dyn Precompile>> {
+        self.contracts.get(&address).cloned()
+    }
+
+    // Return iterator over addresses with a precompile
+    pub fn addresses(&self) -> impl Iterator<Item = &U160> {
+        self.contracts.keys()
+    }
+}
+
+// Standard precompiles with their address and the fork that introduced them
+fn standard() -> Vec<(u8, SpecId, Rc<dyn Precompile>)> {
+    vec![
+        (0x01, SpecId::Frontier, Rc::new(EcRecover)),
+        (0x02, SpecId::Frontier, Rc::new(Sha256)),
+        (0x03, SpecId::Frontier, Rc::new(Ripemd160)),
+        (0x04, SpecId::Frontier, Rc::new(Identity)),
+    ]
+}
+
+// Cost of base plus per_word for every 32-byte word of input
+fn word_gas(input: &[u8], base: usize, per_word: usize) -> usize {
+    base + per_word * input.len().div_ceil(32)
+}
+
+// Return input[offset..offset + N], zero-padded past the end of input
+fn padded<const N: usize>(input: &[u8], offset: usize) -> [u8; N] {
+    let mut ret = [0u8; N];
+    if offset < input.len() {
+        let end = input.len().min(offset + N);
+        ret[..end - offset].copy_from_slice(&input[offset..end]);
+    };
+    ret
+}
+
+// 0x01: recover signer address of a secp256k1 signature over a hash.
+// Input is hash, v, r, s as 32-byte words; invalid signatures return empty output
+pub struct EcRecover;
+impl EcRecover {
+    // Recover left-padded signer address from 128-byte input
+    fn recover(input: &[u8; 128]) -> Option<Vec<u8>> {
+        // v must be exactly 27 or 28 as a 32-byte word
+        if input[32..63].iter().any(|byte| *byte != 0) { return None; };
+        let v = match input[63] {
+            v @ (27 | 28) => v,
+            _ => return None,
+        };
+        let mut recovery_id = RecoveryId::new(v == 28, false);
+        let mut signature = Signature::from_slice(&input[64..128]).ok()?;
+        // Recovery requires low s; negating s flips the parity of the recovered point
+        if let Some(normalized) = signature.normalize_s() {
+            signature = normalized;
+            recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+        };
+        let key = VerifyingKey::recover_from_prehash(&input[..32], &signature, recovery_id).ok()?;
+        let point = key.to_encoded_point(false);
+        let mut ret = keccak256(&point.as_bytes()[1..]).as_slice();
+        ret[..12].fill(0);
+        Some(ret.to_vec())
+    }
+}
+impl Precompile for EcRecover {
+    fn gas(&self, _input: &[u8]) -> usize {
+        3000
+    }
+
+    fn run(&self, input: &[u8]) -> Result<Vec<u8>, StatusCode> {
+        Ok(EcRecover::recover(&padded(input, 0)).unwrap_or_default())
+    }
+}
+
+// 0x02: SHA-256 digest of input
+pub struct Sha256;
+impl Precompile for Sha256 {
+    fn gas(&self, input: &[u8]) -> usize {
+        word_gas(input, 60, 12)
+    }
+
+    fn run(&self, input: &[u8]) -> Result<Vec<u8>, StatusCode> {
+        Ok(Sha256Hasher::digest(input).to_vec())
+    }
+}
+
+// 0x03: RIPEMD-160 digest of input, left-padded to 32 bytes
+pub struct Ripemd160;
+impl Precompile for Ripemd160 {
+    fn gas(&self, input: &[u8]) -> usize {
+        word_gas(input, 600, 120)
+    }
+
+    fn run(&self, input: &[u8]) -> Result<Vec<u8>, StatusCode> {
+        let mut ret = vec![0u8; 12];
+        ret.extend_from_slice(&Ripemd160Hasher::digest(input));
+        Ok(ret)
+    }
+}
+
+// 0x04: return input unchanged
+pub struct Identity;
+impl Precompile for Identity {
+    fn gas(&self, input: &[u8]) -> usize {
+        word_gas(input, 15, 3)
+    }
+
+    fn run(&self, input: &[u8]) -> Result<Vec<u8>, StatusCode> {
+        Ok(input.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::state::{Account, Block, Transaction};
+    use super::super::transact::transact_with_precompiles;
+    use super::super::world_state::WorldState;
+    use ethnum::u256;
+
+    // Signature over hash 0x456e9a... by 0x7156526fbd7a3c72969b54f64e42c10fbb768c8a
+    const ECRECOVER_INPUT: &str = concat!(
+        "456e9aea5e197a1f1af7a3e85a3212fa4049a3ba34c2289b4c860fc0b0c64ef3",
+        "000000000000000000000000000000000000000000000000000000000000001c",
+        "9242685bf161793cc25603c231bc2f568eb630ea16aa137d2664ac8038825608",
+        "4f8ae3bd7535248d0bd448298cc2e2071e56992d0774dc340c368ae950852ada",
+    );
+
+    #[test]
+    fn ecrecover_returns_left_padded_signer() {
+        let input = hex::decode(ECRECOVER_INPUT).unwrap();
+        let output = EcRecover.run(&input).unwrap();
+        assert_eq!(hex::encode(output), "0000000000000000000000007156526fbd7a3c72969b54f64e42c10fbb768c8a");
+        assert_eq!(EcRecover.gas(&input), 3000);
+    }
+
+    #[test]
+    fn ecrecover_rejects_v_other_than_27_or_28() {
+        let mut input = hex::decode(ECRECOVER_INPUT).unwrap();
+        for v in [0, 1, 26, 29, 30] {
+            input[63] = v;
+            assert!(EcRecover.run(&input).unwrap().is_empty());
+        }
+        // x-reduced recovery ids must not be reachable through v = 29 or 30
+        let mut input = [0u8; 128];
+        input[..32].fill(0x42);
+        input[63] = 29;
+        input[127] = 1;
+        for r in 1..40 {
+            input[95] = r;
+            assert!(EcRecover.run(&input).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn ecrecover_of_empty_input_is_empty() {
+        assert!(EcRecover.run(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sha256_of_empty_input() {
+        let output = Sha256.run(&[]).unwrap();
+        assert_eq!(hex::encode(output), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(Sha256.gas(&[]), 60);
+        assert_eq!(Sha256.gas(&[0; 33]), 84);
+    }
+
+    #[test]
+    fn ripemd160_of_empty_input_is_left_padded() {
+        let output = Ripemd160.run(&[]).unwrap();
+        assert_eq!(hex::encode(output), "0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31");
+        assert_eq!(Ripemd160.gas(&[0; 32]), 720);
+    }
+
+    #[test]
+    fn identity_copies_input() {
+        assert_eq!(Identity.run(&[1, 2, 3]).unwrap(), vec![1, 2, 3]);
+        assert_eq!(Identity.gas(&[1, 2, 3]), 18);
+    }
+
+    // Returns its input reversed for 50 gas plus 10 per byte; fails on empty input
+    struct Reverse;
+    impl Precompile for Reverse {
+        fn gas(&self, input: &[u8]) -> usize {
+            50 + 10 * input.len()
+        }
+        fn run(&self, input: &[u8]) -> Result<Vec<u8>, StatusCode> {
+            if input.is_empty() { return Err(StatusCode::PrecompileFailure); };
+            Ok(input.iter().rev().copied().collect())
+        }
+    }
+
+    // Run transaction to recipient with data, with Reverse registered at 0xABCD
+    fn transact_reverse(code: &str, recipient: U160, data: &[u8]) -> (bool, usize, Vec<u8>) {
+        let mut state = WorldState::init();
+        let sender = U160::from_slice(&[0xa0]);
+        state.insert_account(sender, Account::init(0, u256::from(1_000_000u64), Vec::new()));
+        state.insert_account(U160::from_slice(&[0xb0]), Account::init(1, u256::ZERO, hex::decode(code).unwrap()));
+        let mut precompiles = Precompiles::for_spec(SpecId::Prague);
+        precompiles.register(U160::from_slice(&[0xab, 0xcd]), Rc::new(Reverse));
+        let tx = Transaction {
+            chain_id: Some(1),
+            sender,
+            recipient: Some(recipient),
+            data: data.to_vec(),
+            gaslimit: 100_000,
+            maxgas: 1,
+            ..Transaction::default()
+        };
+        let block = Block::default();
+        let result = transact_with_precompiles(&mut state, &tx, &block, SpecId::Prague, precompiles).unwrap();
+        (result.is_success(), result.gas_used, result.output)
+    }
+
+    #[test]
+    fn custom_precompile_called_by_transaction() {
+        let precompile = U160::from_slice(&[0xab, 0xcd]);
+        let (success, gas_used, output) = transact_reverse("", precompile, &[1, 2, 3]);
+        assert!(success);
+        assert_eq!(output, vec![3, 2, 1]);
+        // Intrinsic gas with three non-zero bytes, then the precompile's own cost
+        assert_eq!(gas_used, 21_000 + 3 * 16 + 50 + 3 * 10);
+        // Failures consume all gas
+        let (success, gas_used, output) = transact_reverse("", precompile, &[]);
+        assert!(!success);
+        assert_eq!(gas_used, 100_000);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn custom_precompile_reached_by_call_and_delegatecall() {
+        // CALLDATACOPY(0, 0, CALLDATASIZE), then {CALL, DELEGATECALL}(1000, 0xABCD, [0,] 0, CALLDATASIZE, 0,
+        // CALLDATASIZE), MSTORE(32, success) RETURN(0, 64)
+        let call = "366000600037366000366000600061abcd6103e8f160205260406000f3";
+        let delegatecall = "36600060003736600036600061abcd6103e8f460205260406000f3";
+        let contract = U160::from_slice(&[0xb0]);
+        for code in [call, delegatecall] {
+            let (success, ok_gas, output) = transact_reverse(code, contract, &[1, 2]);
+            assert!(success);
+            assert_eq!(output[..32], [&[2, 1], &[0; 30][..]].concat());
+            assert_eq!(output[32..], [&[0; 31][..], &[1]].concat());
+
+            // Empty input fails and consumes the 1000 gas given to the call instead of 70, while the
+            // transaction saves 32 gas of calldata and 3 of copying
+            let (success, failed_gas, output) = transact_reverse(code, contract, &[]);
+            assert!(success);
+            assert_eq!(output[32..], [0; 32]);
+            assert_eq!(failed_gas - ok_gas, 1000 - 70 - 32 - 3);
+
+            // 96 bytes need 1010 gas, more than the call is given
+            let (success, _, output) = transact_reverse(code, contract, &[1; 96]);
+            assert!(success);
+            assert_eq!(output[32..], [0; 32]);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use super::custom_type::U160;
+use super::state::{AccessListItem, Log};
+use super::statuscode::StatusCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Reason execution halted
+pub enum HaltReason {
+    // STOP, RETURN or end of code
+    Success,
+    // REVERT: state rolled back, remaining gas kept
+    Revert,
+    // Exceptional halt: state rolled back, all gas consumed
+    Exceptional(StatusCode),
+}
+
+#[derive(Debug, Clone)]
+// Execution Result
+pub struct ExecutionResult {
+    pub reason: HaltReason,
+    pub gas_used: usize,
+    pub gas_refunded: usize,
+    // RETURN data, or REVERT payload
+    pub output: Vec<u8>,
+    // Logs emitted by a successful top-level run
+    pub logs: Vec<Log>,
+    // Addresses and storage keys accessed by a top-level run, excluding caller, callee and coinbase (eth_createAccessList)
+    pub access_list: Vec<AccessListItem>,
+    // Address of contract deployed by a successful creation
+    pub created_address: Option<U160>,
+}
+impl ExecutionResult {
+    // Returns whether execution halted successfully
+    pub fn is_success(&self) -> bool {
+        self.reason == HaltReason::Success
+    }
+
+    // Convert halt reason to status code result
+    pub fn status(&self) -> Result<(), StatusCode> {
+        match self.reason {
+            HaltReason::Success => Ok(()),
+            HaltReason::Revert => Err(StatusCode::Revert),
+            HaltReason::Exceptional(code) => Err(code),
+        }
+    }
+}
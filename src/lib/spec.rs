@@ -0,0 +1,57 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+// Hardfork Specification, in activation order
+pub enum SpecId {
+    Frontier,
+    Homestead,
+    // EIP-150: IO gas repricing, 63/64 call gas forwarding
+    TangerineWhistle,
+    // EIP-160, EIP-161, EIP-170: EXP repricing, state clearing, code size limit
+    SpuriousDragon,
+    // REVERT, RETURNDATA*, STATICCALL
+    Byzantium,
+    // SHL/SHR/SAR, CREATE2, EXTCODEHASH
+    Constantinople,
+    Petersburg,
+    // EIP-1884, EIP-2200: trie-size repricing, net gas metering for SSTORE
+    Istanbul,
+    // EIP-2929, EIP-2930: cold/warm access costs, access lists
+    Berlin,
+    // EIP-3198, EIP-3529, EIP-3541: BASEFEE, reduced refunds, 0xEF code rejection
+    London,
+    // The Merge: DIFFICULTY becomes PREVRANDAO
+    Paris,
+    // EIP-3855, EIP-3860: PUSH0, initcode limit
+    Shanghai,
+    // EIP-1153, EIP-4844, EIP-5656, EIP-6780: transient storage, blobs, MCOPY, SELFDESTRUCT restriction
+    Cancun,
+    #[default]
+    Prague,
+}
+impl SpecId {
+    // Returns whether fork is active under self
+    pub fn is_enabled(self, fork: SpecId) -> bool {
+        self >= fork
+    }
+
+    // Parse fork from its lowercase name
+    pub fn from_name(name: &str) -> Option<Self> {
+        let spec = match name {
+            "frontier" => SpecId::Frontier,
+            "homestead" => SpecId::Homestead,
+            "tangerinewhistle" => SpecId::TangerineWhistle,
+            "spuriousdragon" => SpecId::SpuriousDragon,
+            "byzantium" => SpecId::Byzantium,
+            "constantinople" => SpecId::Constantinople,
+            "petersburg" => SpecId::Petersburg,
+            "istanbul" => SpecId::Istanbul,
+            "berlin" => SpecId::Berlin,
+            "london" => SpecId::London,
+            "paris" | "merge" => SpecId::Paris,
+            "shanghai" => SpecId::Shanghai,
+            "cancun" => SpecId::Cancun,
+            "prague" => SpecId::Prague,
+            _ => return None,
+        };
+        Some(spec)
+    }
+}
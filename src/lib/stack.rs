@@ -54,6 +54,11 @@ impl Stack {
         self.storage.len()
     }
 
+    // Return whether stack is empty
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
     // Return reference to stack vector
     pub fn peek_full(&mut self) -> &Vec<U256BE> {
         &self.storage
@@ -1,65 +1,367 @@
-use super::custom_type::{U160, U256BE};
+use super::custom_type::{keccak256, U160, U256BE};
+use super::opcode::{blob_base_fee_update_fraction, GAS_PER_BLOB, MIN_BLOB_BASE_FEE};
+use super::spec::SpecId;
+use super::storage::Storage;
+use ethnum::u256;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::rc::Rc;
 
+// Source of hashes of previous blocks for BLOCKHASH
+pub trait BlockHashes {
+    // Return hash of block with number, if known
+    fn block_hash(&self, number: u64) -> Option<U256BE>;
+}
+impl BlockHashes for HashMap<u64, U256BE> {
+    fn block_hash(&self, number: u64) -> Option<U256BE> {
+        self.get(&number).copied()
+    }
+}
+
+#[derive(Clone)]
 // Block Object
 pub struct Block {
-    pub blocknumber: usize,
-    pub basegas: usize,
-    pub gaslimit: usize,
-    pub difficulty: usize,
-    // pub nonce: usize,
+    pub number: u64,
+    pub timestamp: u64,
     pub coinbase: U160,
-    pub timestamp: U256BE,
-    /*pub mixhash: U256BE,
-    pub parenthash: U256BE,
-    pub transactions: U256BE,
-    pub stateroot: U256BE,*/
+    pub gaslimit: u64,
+    pub basefee: u256,
+    // Proof-of-work difficulty; replaced by prevrandao after the Merge
+    pub difficulty: u256,
+    pub prevrandao: U256BE,
+    pub excess_blob_gas: u64,
+    // Chain the block belongs to (EIP-155)
+    pub chain_id: u64,
+    // Hashes of previous blocks; only the 256 most recent are visible to BLOCKHASH
+    pub block_hashes: Rc<dyn BlockHashes>,
 }
-impl Block {
-    // Initialize block with arbitrary values for testing
-    pub fn default() -> Self {
+impl Default for Block {
+    // Initialize genesis-like block on mainnet with no base fee
+    fn default() -> Self {
         Block {
-            blocknumber: 1,
-            basegas: 21000,
-            gaslimit: 8000000,
-            difficulty: 6,
-            timestamp: U256BE::from_u8(8),
-            // nonce: 7,
-            coinbase: U256BE::from_u8(4).to_u160(),
-            /*mixhash: U256BE::from_u8(2),
-            parenthash: U256BE::from_u8(3),
-            transactions: U256BE::from_u8(9),
-            stateroot: U256BE::from_u8(5)*/
+            number: 0,
+            timestamp: 0,
+            coinbase: U160::default(),
+            gaslimit: 30_000_000,
+            basefee: u256::ZERO,
+            difficulty: u256::ZERO,
+            prevrandao: U256BE::zero(),
+            excess_blob_gas: 0,
+            chain_id: 1,
+            block_hashes: Rc::new(HashMap::new()),
+        }
+    }
+}
+impl Block {
+    // Parse block from a JSON object; absent fields keep their default values. Numbers may be given
+    // as JSON numbers, decimal strings or 0x-prefixed hex strings, and blockHashes maps numbers to hashes
+    pub fn from_json(json: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(json).ok()?;
+        let object = value.as_object()?;
+        let mut block = Block::default();
+        for (key, value) in object {
+            match key.as_str() {
+                "number" => block.number = json_u64(value)?,
+                "timestamp" => block.timestamp = json_u64(value)?,
+                "coinbase" => block.coinbase = U160::from_slice(&json_bytes(value, 20)?),
+                "gasLimit" => block.gaslimit = json_u64(value)?,
+                "baseFee" => block.basefee = json_u256(value)?,
+                "difficulty" => block.difficulty = json_u256(value)?,
+                "prevrandao" => block.prevrandao = U256BE::from_slice(&json_bytes(value, 32)?),
+                "excessBlobGas" => block.excess_blob_gas = json_u64(value)?,
+                "chainId" => block.chain_id = json_u64(value)?,
+                "blockHashes" => {
+                    let mut hashes = HashMap::new();
+                    for (number, hash) in value.as_object()? {
+                        let number = json_u64(&Value::String(number.clone()))?;
+                        hashes.insert(number, U256BE::from_slice(&json_bytes(hash, 32)?));
+                    }
+                    block.block_hashes = Rc::new(hashes);
+                },
+                _ => return None,
+            };
         }
+        Some(block)
+    }
+
+    // Return hash of block with number if it is one of the 256 most recent blocks, zero otherwise
+    pub fn block_hash(&self, number: u256) -> U256BE {
+        let current = u256::from(self.number);
+        match number < current && current - number <= 256 {
+            true => self.block_hashes.block_hash(number.as_u64()).unwrap_or_default(),
+            false => U256BE::zero(),
+        }
+    }
+
+    // Price of blob gas derived from the excess blob gas (EIP-4844)
+    pub fn blob_basefee(&self, spec: SpecId) -> usize {
+        fake_exponential(MIN_BLOB_BASE_FEE, self.excess_blob_gas as usize, blob_base_fee_update_fraction(spec))
+    }
+}
+
+// Parse JSON number, decimal string or 0x-prefixed hex string as u256
+fn json_u256(value: &Value) -> Option<u256> {
+    match value {
+        Value::Number(number) => number.as_u64().map(u256::from),
+        Value::String(string) => match string.strip_prefix("0x") {
+            Some(hex) => u256::from_str_radix(hex, 16).ok(),
+            None => u256::from_str_radix(string, 10).ok(),
+        },
+        _ => None,
     }
 }
 
-/*// Transaction Object
+// Parse JSON value as u64
+fn json_u64(value: &Value) -> Option<u64> {
+    json_u256(value).filter(|value| *value <= u64::MAX as u128).map(|value| value.as_u64())
+}
+
+// Parse 0x-prefixed hex string of at most len bytes
+fn json_bytes(value: &Value, len: usize) -> Option<Vec<u8>> {
+    let bytes = hex::decode(value.as_str()?.strip_prefix("0x")?).ok()?;
+    if bytes.len() > len { return None; };
+    Some(bytes)
+}
+
+// Integer approximation of factor * e^(numerator / denominator), saturating at usize::MAX
+fn fake_exponential(factor: usize, numerator: usize, denominator: usize) -> usize {
+    let (numerator, denominator) = (u256::from(numerator as u64), u256::from(denominator as u64));
+    let cap = u256::from(usize::MAX as u64) * denominator;
+    let mut output = u256::ZERO;
+    let mut accum = u256::from(factor as u64) * denominator;
+    let mut i = u256::ONE;
+    while accum > 0 && output < cap {
+        output = output.saturating_add(accum);
+        accum = match accum.checked_mul(numerator) {
+            Some(product) => product / (denominator * i),
+            None => return usize::MAX,
+        };
+        i += 1;
+    }
+    (output / denominator).min(u256::from(usize::MAX as u64)).as_usize()
+}
+
+#[derive(Debug, Clone)]
+// Transaction Environment shared by every frame of a transaction. Caller and value are per frame
+// and come from the message
+pub struct TxEnv {
+    pub origin: U160,
+    // Effective gas price paid by the sender
+    pub gasprice: usize,
+    // Versioned hashes of the transaction's blobs (EIP-4844)
+    pub blob_hashes: Vec<U256BE>,
+    // Chain id returned by CHAINID; ExecutionContext::set_block copies it from the block
+    pub chain_id: u64,
+}
+impl Default for TxEnv {
+    // Empty environment on mainnet
+    fn default() -> Self {
+        TxEnv {
+            origin: U160::default(),
+            gasprice: 0,
+            blob_hashes: Vec::new(),
+            chain_id: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+// Transaction envelope type (EIP-2718)
+pub enum TxType {
+    #[default]
+    Legacy,
+    // EIP-2930: access list
+    AccessList,
+    // EIP-1559: priority fee and fee cap
+    DynamicFee,
+    // EIP-4844: blob versioned hashes and blob fee cap
+    Blob,
+    // EIP-7702: authorization list setting delegation code on externally owned accounts
+    SetCode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+// Set-code authorization (EIP-7702); the authority is given directly rather than recovered from a signature
+pub struct Authorization {
+    // Zero authorizes on any chain
+    pub chain_id: u64,
+    // Account whose code the authority delegates to; zero clears the delegation
+    pub address: U160,
+    pub nonce: usize,
+    pub authority: U160,
+}
+
+#[derive(Debug, Clone, Default)]
+// Transaction Object; the sender is given directly rather than recovered from a signature
 pub struct Transaction {
-    recipient: U160,
-    signature: U256BE,
-    value: usize,
-    data: U256BE,
-    gaslimit: usize,
-    maxprioritygas: usize,
-    maxgas: usize
+    pub tx_type: TxType,
+    // None only for legacy transactions without replay protection (pre EIP-155)
+    pub chain_id: Option<u64>,
+    pub sender: U160,
+    // None creates a contract with data as init code
+    pub recipient: Option<U160>,
+    pub nonce: usize,
+    pub value: u256,
+    pub data: Vec<u8>,
+    pub gaslimit: usize,
+    // Maximum priority fee and maximum total fee per gas (EIP-1559).
+    // Legacy and access list transactions pay maxgas as their gas price
+    pub maxprioritygas: usize,
+    pub maxgas: usize,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: usize,
+    pub blob_versioned_hashes: Vec<U256BE>,
+    pub authorization_list: Vec<Authorization>,
+}
+impl Transaction {
+    // Blob gas consumed by the transaction's blobs
+    pub fn blob_gas(&self) -> usize {
+        GAS_PER_BLOB * self.blob_versioned_hashes.len()
+    }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 // Log Object
 pub struct Log {
-    address: U160,
-    blockhash: U256BE,
-    blocknumber: usize,
-    data: Vec<u8>,
-    logindex: usize,
-    topics: Vec<U256BE>,
-    txhash: U256BE,
-    txindex: usize,
+    pub address: U160,
+    pub topics: Vec<U256BE>,
+    pub data: Vec<u8>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+// Access list entry: address and storage keys to pre-warm (EIP-2930)
+pub struct AccessListItem {
+    pub address: U160,
+    pub storage_keys: Vec<U256BE>,
+}
+
+// Code prefix marking an account as delegating to another account's code (EIP-7702)
+pub const DELEGATION_PREFIX: [u8; 3] = [0xEF, 0x01, 0x00];
+
+#[derive(Debug, Clone)]
 // Account Object
 pub struct Account {
-    nonce: usize,
-    balance: usize,
+    pub nonce: usize,
+    pub balance: u256,
+    code: Vec<u8>,
     codehash: U256BE,
-    storageroot: U256BE
-}*/
+    pub storage: Storage,
+}
+impl Account {
+    // Initialize account with empty storage
+    pub fn init(nonce: usize, balance: u256, code: Vec<u8>) -> Self {
+        Account {
+            nonce,
+            balance,
+            codehash: keccak256(&code),
+            code,
+            storage: Storage::init(),
+        }
+    }
+
+    // Return reference to account code
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    // Return keccak256 hash of account code
+    pub fn codehash(&self) -> U256BE {
+        self.codehash
+    }
+
+    // Replace account code and its hash
+    pub fn set_code(&mut self, code: Vec<u8>) {
+        self.codehash = keccak256(&code);
+        self.code = code;
+    }
+
+    // Return account whose code this account delegates to, if its code is a delegation designator (EIP-7702)
+    pub fn delegation(&self) -> Option<U160> {
+        match self.code.len() == 23 && self.code.starts_with(&DELEGATION_PREFIX) {
+            true => Some(U160::from_slice(&self.code[3..])),
+            false => None,
+        }
+    }
+
+    // Returns whether account is empty as defined by EIP-161
+    pub fn is_empty(&self) -> bool {
+        self.nonce == 0 && self.balance == u256::ZERO && self.code.is_empty()
+    }
+}
+impl Default for Account {
+    fn default() -> Self {
+        Account::init(0, u256::ZERO, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blob_basefee_grows_with_excess_blob_gas() {
+        let block = |excess_blob_gas| Block { excess_blob_gas, ..Block::default() };
+        assert_eq!(block(0).blob_basefee(SpecId::Cancun), 1);
+        // EIP-4844 update fraction 3338477: e^(10_000_000 / 3338477) = 19.97
+        assert_eq!(block(10_000_000).blob_basefee(SpecId::Cancun), 19);
+    }
+
+    #[test]
+    fn blob_basefee_saturates_for_large_excess_blob_gas() {
+        let block = Block { excess_blob_gas: 1_000_000_000, ..Block::default() };
+        assert_eq!(block.blob_basefee(SpecId::Cancun), usize::MAX);
+        let block = Block { excess_blob_gas: u64::MAX, ..Block::default() };
+        assert_eq!(block.blob_basefee(SpecId::Prague), usize::MAX);
+    }
+
+    #[test]
+    fn block_from_json_parses_numbers_and_hex() {
+        let json = r#"{
+            "number": 100,
+            "timestamp": "1700000000",
+            "gasLimit": "0x1c9c380",
+            "baseFee": "0x0a",
+            "coinbase": "0x00000000000000000000000000000000000000c0",
+            "prevrandao": "0x01",
+            "chainId": "0x5",
+            "blockHashes": { "99": "0xaa", "0x62": "0xbb" }
+        }"#;
+        let block = Block::from_json(json).unwrap();
+        assert_eq!(block.number, 100);
+        assert_eq!(block.timestamp, 1_700_000_000);
+        assert_eq!(block.gaslimit, 30_000_000);
+        assert_eq!(block.basefee, 10);
+        assert_eq!(block.coinbase, U160::from_slice(&[0xc0]));
+        assert_eq!(block.prevrandao, U256BE::from_u8(1));
+        assert_eq!(block.chain_id, 5);
+        assert_eq!(block.block_hash(u256::from(99u64)), U256BE::from_u8(0xaa));
+        assert_eq!(block.block_hash(u256::from(98u64)), U256BE::from_u8(0xbb));
+        // Absent fields keep their defaults
+        assert_eq!(block.excess_blob_gas, 0);
+    }
+
+    #[test]
+    fn block_from_json_rejects_invalid_input() {
+        assert!(Block::from_json(r#"{ "gasPrice": 1 }"#).is_none());
+        assert!(Block::from_json(r#"{ "number": "0xzz" }"#).is_none());
+        assert!(Block::from_json(r#"{ "number": "0x10000000000000000" }"#).is_none());
+        assert!(Block::from_json(r#"{ "coinbase": "0x000000000000000000000000000000000000000000" }"#).is_none());
+        assert!(Block::from_json("[]").is_none());
+    }
+
+    #[test]
+    fn block_hash_window() {
+        // Block n has hash n + 1
+        let hashes: HashMap<u64, U256BE> = (0..=1000u64).map(|n| (n, U256BE::from_usize(n as usize + 1))).collect();
+        let block = Block { number: 1000, block_hashes: Rc::new(hashes), ..Block::default() };
+        let hash = |number: u64| block.block_hash(u256::from(number));
+        assert_eq!(hash(999), U256BE::from_usize(1000));
+        assert_eq!(hash(1000 - 256), U256BE::from_usize(1000 - 256 + 1));
+        assert_eq!(hash(1000 - 257), U256BE::zero());
+        assert_eq!(hash(1000), U256BE::zero());
+        assert_eq!(hash(1001), U256BE::zero());
+        assert_eq!(block.block_hash(u256::MAX), U256BE::zero());
+        // Unknown hashes within the window are zero
+        assert_eq!(Block { number: 1000, ..Block::default() }.block_hash(u256::from(999u64)), U256BE::zero());
+    }
+}
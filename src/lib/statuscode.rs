@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 // EVM(C) Status Codes
 pub enum StatusCode {
     Completion,
@@ -0,0 +1,50 @@
+use super::custom_type::U256BE;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default)]
+// Storage slot state used for net gas metering (EIP-2200)
+pub struct StorageSlot {
+    // Value at the start of the current transaction
+    pub original: U256BE,
+    // Value as of the last write
+    pub current: U256BE,
+}
+
+#[derive(Debug, Clone, Default)]
+// Per-account persistent key/value storage
+pub struct Storage {
+    // Non-zero slots only; absent keys read as zero
+    slots: HashMap<U256BE, U256BE>,
+}
+impl Storage {
+    // Initialize empty storage
+    pub fn init() -> Self {
+        Storage {
+            slots: HashMap::new(),
+        }
+    }
+
+    // Load value at key
+    pub fn load(&self, key: U256BE) -> U256BE {
+        self.slots.get(&key).copied().unwrap_or_default()
+    }
+
+    // Store value at key, returning the previous value
+    pub fn store(&mut self, key: U256BE, value: U256BE) -> U256BE {
+        let previous = match value.is_zero() {
+            true => self.slots.remove(&key),
+            false => self.slots.insert(key, value),
+        };
+        previous.unwrap_or_default()
+    }
+
+    // Returns whether no slot holds a non-zero value
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    // Return iterator over non-zero (key, value) pairs
+    pub fn iter(&self) -> impl Iterator<Item = (&U256BE, &U256BE)> {
+        self.slots.iter()
+    }
+}
@@ -0,0 +1,586 @@
+use super::execution::{create_address, ExecutionContext};
+use super::memory::Memory;
+use super::message::{CallKind, Message};
+use super::opcode::*;
+use super::precompile::Precompiles;
+use super::result::ExecutionResult;
+use super::spec::SpecId;
+use super::stack::Stack;
+use super::custom_type::U160;
+use super::state::{Authorization, Block, Transaction, TxEnv, TxType, DELEGATION_PREFIX};
+use super::world_state::WorldState;
+use ethnum::u256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Reason a transaction is rejected before execution; rejected transactions leave the state untouched
+pub enum InvalidTransaction {
+    NonceMismatch,
+    // Sender nonce cannot be incremented further (EIP-2681)
+    NonceMaxValue,
+    IntrinsicGasTooLow,
+    GasLimitExceedsBlock,
+    FeeCapBelowBaseFee,
+    PriorityFeeAboveFeeCap,
+    InsufficientFunds,
+    SenderHasCode,
+    InitcodeTooLarge,
+    // Transaction type not active in the configured fork
+    TypeNotSupported,
+    ChainIdMismatch,
+    // Field set that the transaction type does not carry
+    UnexpectedField,
+    // Blob and set-code transactions cannot create contracts
+    CreateNotAllowed,
+    BlobCountInvalid,
+    BlobVersionInvalid,
+    BlobFeeCapBelowBlobBaseFee,
+    EmptyAuthorizationList,
+}
+
+// Convert usize to u256
+fn to_u256(value: usize) -> u256 {
+    u256::from(value as u64)
+}
+
+// Number of calldata tokens: zero bytes count one, non-zero bytes four (EIP-7623)
+fn data_tokens(data: &[u8]) -> usize {
+    let zeros = data.iter().filter(|byte| **byte == 0).count();
+    zeros + (data.len() - zeros) * 4
+}
+
+// Gas charged before execution: base cost, calldata, access list and creation costs
+pub fn intrinsic_gas(tx: &Transaction, spec: SpecId) -> usize {
+    let zeros = tx.data.iter().filter(|byte| **byte == 0).count();
+    let mut gas = TX_GAS + zeros * TX_DATA_ZERO_GAS + (tx.data.len() - zeros) * tx_data_non_zero_gas(spec);
+    if tx.recipient.is_none() {
+        if spec.is_enabled(SpecId::Homestead) { gas += TX_CREATE_GAS; };
+        if spec.is_enabled(SpecId::Shanghai) { gas += INITCODE_WORD_GAS * tx.data.len().div_ceil(32); };
+    };
+    for item in &tx.access_list {
+        gas += ACCESS_LIST_ADDRESS_GAS + ACCESS_LIST_STORAGE_KEY_GAS * item.storage_keys.len();
+    }
+    gas + PER_EMPTY_ACCOUNT_COST * tx.authorization_list.len()
+}
+
+// Minimum gas a transaction is charged for its calldata (EIP-7623)
+pub fn floor_gas(tx: &Transaction, spec: SpecId) -> usize {
+    match spec.is_enabled(SpecId::Prague) {
+        true => TX_GAS + TOTAL_COST_FLOOR_PER_TOKEN * data_tokens(&tx.data),
+        false => 0,
+    }
+}
+
+// Gas price paid per unit of gas: base fee plus priority fee, capped by the fee cap (EIP-1559)
+pub fn effective_gas_price(tx: &Transaction, block: &Block, spec: SpecId) -> usize {
+    match tx.tx_type {
+        TxType::Legacy | TxType::AccessList => tx.maxgas,
+        _ if !spec.is_enabled(SpecId::London) => tx.maxgas,
+        _ => match block.basefee.saturating_add(to_u256(tx.maxprioritygas)) {
+            price if price < to_u256(tx.maxgas) => price.as_usize(),
+            _ => tx.maxgas,
+        },
+    }
+}
+
+// Check fields of the transaction's envelope type against the fork and block
+fn validate_type(tx: &Transaction, block: &Block, spec: SpecId) -> Result<(), InvalidTransaction> {
+    let introduced = match tx.tx_type {
+        TxType::Legacy => SpecId::Frontier,
+        TxType::AccessList => SpecId::Berlin,
+        TxType::DynamicFee => SpecId::London,
+        TxType::Blob => SpecId::Cancun,
+        TxType::SetCode => SpecId::Prague,
+    };
+    if !spec.is_enabled(introduced) { return Err(InvalidTransaction::TypeNotSupported); };
+    // Legacy transactions may omit the chain id; replay protection needs EIP-155
+    match tx.chain_id {
+        Some(chain_id) if chain_id != block.chain_id => return Err(InvalidTransaction::ChainIdMismatch),
+        Some(_) if !spec.is_enabled(SpecId::SpuriousDragon) => return Err(InvalidTransaction::TypeNotSupported),
+        None if tx.tx_type != TxType::Legacy => return Err(InvalidTransaction::ChainIdMismatch),
+        _ => (),
+    };
+    if tx.tx_type == TxType::Legacy && !tx.access_list.is_empty() { return Err(InvalidTransaction::UnexpectedField); };
+    if tx.tx_type != TxType::Blob && (!tx.blob_versioned_hashes.is_empty() || tx.max_fee_per_blob_gas != 0) {
+        return Err(InvalidTransaction::UnexpectedField);
+    };
+    if tx.tx_type != TxType::SetCode && !tx.authorization_list.is_empty() {
+        return Err(InvalidTransaction::UnexpectedField);
+    };
+    // Legacy and access list transactions carry a single gas price in maxgas
+    let dynamic_fee = !matches!(tx.tx_type, TxType::Legacy | TxType::AccessList);
+    if dynamic_fee && tx.maxprioritygas > tx.maxgas { return Err(InvalidTransaction::PriorityFeeAboveFeeCap); };
+    if spec.is_enabled(SpecId::London) && to_u256(tx.maxgas) < block.basefee {
+        return Err(InvalidTransaction::FeeCapBelowBaseFee);
+    };
+    match tx.tx_type {
+        TxType::Blob => {
+            if tx.recipient.is_none() { return Err(InvalidTransaction::CreateNotAllowed); };
+            let blobs = tx.blob_versioned_hashes.len();
+            if blobs == 0 || blobs > max_blobs_per_block(spec) { return Err(InvalidTransaction::BlobCountInvalid); };
+            if tx.blob_versioned_hashes.iter().any(|hash| hash.as_slice()[0] != VERSIONED_HASH_VERSION_KZG) {
+                return Err(InvalidTransaction::BlobVersionInvalid);
+            };
+            if tx.max_fee_per_blob_gas < block.blob_basefee(spec) {
+                return Err(InvalidTransaction::BlobFeeCapBelowBlobBaseFee);
+            };
+        },
+        TxType::SetCode => {
+            if tx.recipient.is_none() { return Err(InvalidTransaction::CreateNotAllowed); };
+            if tx.authorization_list.is_empty() { return Err(InvalidTransaction::EmptyAuthorizationList); };
+        },
+        _ => (),
+    };
+    Ok(())
+}
+
+// Check transaction against sender account and block before any state change
+fn validate(state: &WorldState, tx: &Transaction, block: &Block, spec: SpecId) -> Result<(), InvalidTransaction> {
+    validate_type(tx, block, spec)?;
+    if tx.nonce != state.nonce(tx.sender) { return Err(InvalidTransaction::NonceMismatch); };
+    if tx.nonce == u64::MAX as usize { return Err(InvalidTransaction::NonceMaxValue); };
+    // Senders must be externally owned accounts, which may carry a delegation designator (EIP-3607, EIP-7702)
+    if !state.code(tx.sender).is_empty() && state.delegation(tx.sender).is_none() {
+        return Err(InvalidTransaction::SenderHasCode);
+    };
+    if tx.gaslimit as u64 > block.gaslimit { return Err(InvalidTransaction::GasLimitExceedsBlock); };
+    if tx.gaslimit < intrinsic_gas(tx, spec).max(floor_gas(tx, spec)) {
+        return Err(InvalidTransaction::IntrinsicGasTooLow);
+    };
+    if tx.recipient.is_none() && spec.is_enabled(SpecId::Shanghai) && tx.data.len() > MAX_INITCODE_SIZE {
+        return Err(InvalidTransaction::InitcodeTooLarge);
+    };
+    // The sender must afford the gas limit and blob gas at their fee caps plus the value
+    let cost = to_u256(tx.gaslimit) * to_u256(tx.maxgas) + to_u256(tx.blob_gas()) * to_u256(tx.max_fee_per_blob_gas);
+    if state.balance(tx.sender) < cost + tx.value { return Err(InvalidTransaction::InsufficientFunds); };
+    Ok(())
+}
+
+// Apply set-code authorization, returning the refund owed if the authority already existed.
+// Invalid authorizations are skipped without failing the transaction (EIP-7702)
+fn apply_authorization(state: &mut WorldState, auth: &Authorization, chain_id: u64) -> Option<usize> {
+    if auth.chain_id != 0 && auth.chain_id != chain_id { return None; };
+    if auth.nonce == u64::MAX as usize { return None; };
+    state.access_address(auth.authority);
+    if !state.code(auth.authority).is_empty() && state.delegation(auth.authority).is_none() { return None; };
+    if state.nonce(auth.authority) != auth.nonce { return None; };
+    let refund = match state.exists(auth.authority) {
+        true => PER_EMPTY_ACCOUNT_COST - PER_AUTH_BASE_COST,
+        false => 0,
+    };
+    let code = match auth.address == U160::default() {
+        true => Vec::new(),
+        false => [&DELEGATION_PREFIX[..], &auth.address.as_slice()].concat(),
+    };
+    state.set_code(auth.authority, code);
+    state.set_nonce(auth.authority, auth.nonce + 1);
+    Some(refund)
+}
+
+// Validate and execute transaction in block: buy gas, run the call or creation, apply the refund,
+// return unused gas to the sender and pay the priority fee to the coinbase. The base fee is burned
+pub fn transact(
+    state: &mut WorldState,
+    tx: &Transaction,
+    block: &Block,
+    spec: SpecId,
+) -> Result<ExecutionResult, InvalidTransaction> {
+    transact_with_precompiles(state, tx, block, spec, Precompiles::for_spec(spec))
+}
+
+// Validate and execute transaction as transact() does, dispatching calls to the given precompiles
+pub fn transact_with_precompiles(
+    state: &mut WorldState,
+    tx: &Transaction,
+    block: &Block,
+    spec: SpecId,
+    precompiles: Precompiles,
+) -> Result<ExecutionResult, InvalidTransaction> {
+    validate(state, tx, block, spec)?;
+    let gas_price = effective_gas_price(tx, block, spec);
+    // Blob gas is bought at the blob base fee and burned (EIP-4844)
+    let blob_fee = match tx.blob_gas() {
+        0 => u256::ZERO,
+        blob_gas => to_u256(blob_gas) * to_u256(block.blob_basefee(spec)),
+    };
+    let balance = state.balance(tx.sender);
+    state.set_balance(tx.sender, balance - to_u256(tx.gaslimit) * to_u256(gas_price) - blob_fee);
+    state.set_nonce(tx.sender, tx.nonce + 1);
+    if spec.is_enabled(SpecId::Berlin) { state.warm_access_list(&tx.access_list); };
+    let auth_refund: usize = tx.authorization_list
+        .iter()
+        .filter_map(|auth| apply_authorization(state, auth, block.chain_id))
+        .sum();
+
+    let intrinsic = intrinsic_gas(tx, spec);
+    let message = match tx.recipient {
+        Some(recipient) => Message::call(tx.sender, recipient, tx.value, tx.data.clone(), tx.gaslimit - intrinsic),
+        None => {
+            let address = create_address(tx.sender, tx.nonce);
+            Message {
+                kind: CallKind::Create,
+                code_address: address,
+                address,
+                ..Message::call(tx.sender, address, tx.value, tx.data.clone(), tx.gaslimit - intrinsic)
+            }
+        },
+    };
+    let mut context = ExecutionContext::init(message, Stack::init(), Memory::init(), state, spec);
+    context.set_block(block.clone());
+    context.set_precompiles(precompiles);
+    context.defer_end_transaction();
+    context.set_env(TxEnv {
+        origin: tx.sender,
+        gasprice: gas_price,
+        blob_hashes: tx.blob_versioned_hashes.clone(),
+        chain_id: block.chain_id,
+    });
+    let mut result = context.run();
+
+    // Refunds are capped at a fifth of the gas used, half before London (EIP-3529)
+    let gas_used = intrinsic + result.gas_used;
+    let refund_quotient = if spec.is_enabled(SpecId::London) { 5 } else { 2 };
+    let refund = match result.is_success() {
+        true => result.gas_refunded + auth_refund,
+        false => auth_refund,
+    };
+    let refund = refund.min(gas_used / refund_quotient);
+    let gas_used = (gas_used - refund).max(floor_gas(tx, spec));
+    let sender_balance = state.balance(tx.sender);
+    state.set_balance(tx.sender, sender_balance + to_u256(tx.gaslimit - gas_used) * to_u256(gas_price));
+    let priority_fee = match spec.is_enabled(SpecId::London) {
+        true => to_u256(gas_price) - block.basefee,
+        false => to_u256(gas_price),
+    };
+    let fee = to_u256(gas_used) * priority_fee;
+    if fee != u256::ZERO { state.set_balance(block.coinbase, state.balance(block.coinbase) + fee); };
+    // Self-destructed accounts, including a self-destructed coinbase, are deleted only after fees are paid
+    state.end_transaction();
+    result.gas_used = gas_used;
+    result.gas_refunded = refund;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::custom_type::U256BE;
+    use super::super::execution::create2_address;
+    use super::super::state::{AccessListItem, Account};
+
+    fn addr(hex: &str) -> U160 {
+        U160::from_slice(&hex::decode(hex).unwrap())
+    }
+
+    fn setup(sender_balance: u64) -> (WorldState, Block) {
+        let mut state = WorldState::init();
+        state.insert_account(addr("a0"), Account::init(0, u256::from(sender_balance), Vec::new()));
+        let block = Block { coinbase: addr("c0"), ..Block::default() };
+        (state, block)
+    }
+
+    // Transaction of tx_type that passes validation in Prague
+    fn typed_tx(tx_type: TxType) -> Transaction {
+        let mut tx = Transaction {
+            tx_type,
+            chain_id: Some(1),
+            sender: addr("a0"),
+            recipient: Some(addr("b0")),
+            gaslimit: 100_000,
+            maxgas: 1,
+            ..Transaction::default()
+        };
+        if tx_type == TxType::Blob {
+            tx.blob_versioned_hashes = vec![U256BE::from_slice(&[VERSIONED_HASH_VERSION_KZG; 32])];
+            tx.max_fee_per_blob_gas = 1;
+        };
+        if tx_type == TxType::SetCode {
+            let auth = Authorization { chain_id: 1, address: addr("d0"), nonce: 0, authority: addr("e0") };
+            tx.authorization_list = vec![auth];
+        };
+        tx
+    }
+
+    // Run transaction expected to be rejected, checking it left the sender untouched
+    fn reject(tx: &Transaction, block: &Block, spec: SpecId) -> InvalidTransaction {
+        let (mut state, _) = setup(1_000_000);
+        let error = transact(&mut state, tx, block, spec).unwrap_err();
+        assert_eq!(state.balance(addr("a0")), 1_000_000);
+        assert_eq!(state.nonce(addr("a0")), 0);
+        error
+    }
+
+    #[test]
+    fn value_transfer() {
+        let (mut state, block) = setup(1_000_000);
+        let tx = Transaction {
+            chain_id: Some(1),
+            sender: addr("a0"),
+            recipient: Some(addr("b0")),
+            value: u256::from(1000u64),
+            gaslimit: 30_000,
+            maxgas: 10,
+            ..Transaction::default()
+        };
+        let result = transact(&mut state, &tx, &block, SpecId::Prague).unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.gas_used, 21_000);
+        assert_eq!(state.balance(addr("a0")), 1_000_000 - 1000 - 21_000 * 10);
+        assert_eq!(state.balance(addr("b0")), 1000);
+        assert_eq!(state.balance(addr("c0")), 21_000 * 10);
+        assert_eq!(state.nonce(addr("a0")), 1);
+    }
+
+    #[test]
+    fn create_transaction() {
+        let (mut state, block) = setup(1_000_000);
+        // MSTORE8(0, 0x01) RETURN(0, 1)
+        let tx = Transaction {
+            chain_id: Some(1),
+            sender: addr("a0"),
+            data: hex::decode("600160005360016000f3").unwrap(),
+            gaslimit: 100_000,
+            maxgas: 1,
+            ..Transaction::default()
+        };
+        let result = transact(&mut state, &tx, &block, SpecId::Prague).unwrap();
+        let address = create_address(addr("a0"), 0);
+        assert!(result.is_success());
+        assert_eq!(result.created_address, Some(address));
+        assert_eq!(state.code(address), &[0x01]);
+        assert_eq!(state.nonce(address), 1);
+        assert_eq!(state.nonce(addr("a0")), 1);
+    }
+
+    #[test]
+    fn sstore_clear_refund_is_capped() {
+        let (mut state, block) = setup(1_000_000);
+        // SSTORE(0, 1) SSTORE(0, 0) STOP
+        state.insert_account(addr("b0"), Account::init(1, u256::ZERO, hex::decode("6001600055600060005500").unwrap()));
+        let tx = Transaction {
+            tx_type: TxType::DynamicFee,
+            chain_id: Some(1),
+            sender: addr("a0"),
+            recipient: Some(addr("b0")),
+            gaslimit: 100_000,
+            maxprioritygas: 2,
+            maxgas: 2,
+            ..Transaction::default()
+        };
+        let result = transact(&mut state, &tx, &block, SpecId::Prague).unwrap();
+        // 21000 intrinsic + 22212 execution, of which a fifth (8642) is refunded instead of 19900
+        assert!(result.is_success());
+        assert_eq!(result.gas_refunded, 43_212 / 5);
+        assert_eq!(result.gas_used, 43_212 - 43_212 / 5);
+        assert_eq!(state.sload(addr("b0"), U256BE::zero()), U256BE::zero());
+        assert_eq!(state.balance(addr("a0")), 1_000_000 - 2 * to_u256(result.gas_used));
+    }
+
+    #[test]
+    fn typed_transactions_are_valid() {
+        let block = Block::default();
+        for tx_type in [TxType::Legacy, TxType::AccessList, TxType::DynamicFee, TxType::Blob, TxType::SetCode] {
+            let (mut state, _) = setup(1_000_000);
+            assert!(transact(&mut state, &typed_tx(tx_type), &block, SpecId::Prague).is_ok());
+        }
+    }
+
+    #[test]
+    fn type_not_supported_before_its_fork() {
+        let block = Block::default();
+        let forks = [
+            (TxType::AccessList, SpecId::Istanbul),
+            (TxType::DynamicFee, SpecId::Berlin),
+            (TxType::Blob, SpecId::Shanghai),
+            (TxType::SetCode, SpecId::Cancun),
+        ];
+        for (tx_type, spec) in forks {
+            assert_eq!(reject(&typed_tx(tx_type), &block, spec), InvalidTransaction::TypeNotSupported);
+        }
+    }
+
+    #[test]
+    fn chain_id_mismatch() {
+        let block = Block::default();
+        let tx = Transaction { chain_id: Some(5), ..typed_tx(TxType::Legacy) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::ChainIdMismatch);
+        // Only legacy transactions may omit the chain id
+        let tx = Transaction { chain_id: None, ..typed_tx(TxType::DynamicFee) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::ChainIdMismatch);
+        let (mut state, _) = setup(1_000_000);
+        let tx = Transaction { chain_id: None, ..typed_tx(TxType::Legacy) };
+        assert!(transact(&mut state, &tx, &block, SpecId::Prague).is_ok());
+    }
+
+    #[test]
+    fn chain_id_requires_spurious_dragon() {
+        let block = Block::default();
+        assert_eq!(reject(&typed_tx(TxType::Legacy), &block, SpecId::Homestead), InvalidTransaction::TypeNotSupported);
+        let (mut state, _) = setup(1_000_000);
+        let tx = Transaction { chain_id: None, ..typed_tx(TxType::Legacy) };
+        assert!(transact(&mut state, &tx, &block, SpecId::Homestead).is_ok());
+    }
+
+    #[test]
+    fn unexpected_fields() {
+        let block = Block::default();
+        let access_list = vec![AccessListItem { address: addr("b0"), storage_keys: Vec::new() }];
+        let tx = Transaction { access_list, ..typed_tx(TxType::Legacy) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::UnexpectedField);
+        let blob_versioned_hashes = typed_tx(TxType::Blob).blob_versioned_hashes;
+        let tx = Transaction { blob_versioned_hashes, ..typed_tx(TxType::DynamicFee) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::UnexpectedField);
+        let tx = Transaction { max_fee_per_blob_gas: 1, ..typed_tx(TxType::DynamicFee) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::UnexpectedField);
+        let authorization_list = typed_tx(TxType::SetCode).authorization_list;
+        let tx = Transaction { authorization_list, ..typed_tx(TxType::Blob) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::UnexpectedField);
+    }
+
+    #[test]
+    fn priority_fee_above_fee_cap() {
+        let tx = Transaction { maxprioritygas: 2, ..typed_tx(TxType::DynamicFee) };
+        assert_eq!(reject(&tx, &Block::default(), SpecId::Prague), InvalidTransaction::PriorityFeeAboveFeeCap);
+    }
+
+    #[test]
+    fn fee_cap_below_base_fee() {
+        let block = Block { basefee: u256::from(2u64), ..Block::default() };
+        let error = reject(&typed_tx(TxType::DynamicFee), &block, SpecId::Prague);
+        assert_eq!(error, InvalidTransaction::FeeCapBelowBaseFee);
+    }
+
+    #[test]
+    fn blob_and_set_code_transactions_cannot_create() {
+        let block = Block::default();
+        let tx = Transaction { recipient: None, ..typed_tx(TxType::Blob) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::CreateNotAllowed);
+        let tx = Transaction { recipient: None, ..typed_tx(TxType::SetCode) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::CreateNotAllowed);
+    }
+
+    #[test]
+    fn blob_count_invalid() {
+        let block = Block::default();
+        let tx = Transaction { blob_versioned_hashes: Vec::new(), ..typed_tx(TxType::Blob) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::BlobCountInvalid);
+        let hash = U256BE::from_slice(&[VERSIONED_HASH_VERSION_KZG; 32]);
+        let blob_versioned_hashes = vec![hash; max_blobs_per_block(SpecId::Prague) + 1];
+        let tx = Transaction { blob_versioned_hashes, ..typed_tx(TxType::Blob) };
+        assert_eq!(reject(&tx, &block, SpecId::Prague), InvalidTransaction::BlobCountInvalid);
+    }
+
+    #[test]
+    fn blob_version_invalid() {
+        let tx = Transaction { blob_versioned_hashes: vec![U256BE::from_slice(&[0x02; 32])], ..typed_tx(TxType::Blob) };
+        assert_eq!(reject(&tx, &Block::default(), SpecId::Prague), InvalidTransaction::BlobVersionInvalid);
+    }
+
+    #[test]
+    fn blob_fee_cap_below_blob_base_fee() {
+        let block = Block { excess_blob_gas: 10_000_000, ..Block::default() };
+        let error = reject(&typed_tx(TxType::Blob), &block, SpecId::Cancun);
+        assert_eq!(error, InvalidTransaction::BlobFeeCapBelowBlobBaseFee);
+    }
+
+    #[test]
+    fn empty_authorization_list() {
+        let tx = Transaction { authorization_list: Vec::new(), ..typed_tx(TxType::SetCode) };
+        assert_eq!(reject(&tx, &Block::default(), SpecId::Prague), InvalidTransaction::EmptyAuthorizationList);
+    }
+
+    #[test]
+    fn invalid_authorizations_are_skipped() {
+        let (mut state, block) = setup(1_000_000);
+        state.insert_account(addr("e1"), Account::init(0, u256::ZERO, vec![0x00]));
+        let auth = Authorization { chain_id: 1, address: addr("d0"), nonce: 0, authority: addr("e0") };
+        let authorization_list = vec![
+            Authorization { chain_id: 5, ..auth },
+            Authorization { nonce: 1, ..auth },
+            Authorization { nonce: u64::MAX as usize, ..auth },
+            // Authorities with code other than a delegation cannot delegate
+            Authorization { authority: addr("e1"), ..auth },
+        ];
+        let tx = Transaction { authorization_list, gaslimit: 200_000, ..typed_tx(TxType::SetCode) };
+        let result = transact(&mut state, &tx, &block, SpecId::Prague).unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.gas_used, 21_000 + 4 * PER_EMPTY_ACCOUNT_COST);
+        assert!(state.code(addr("e0")).is_empty());
+        assert_eq!(state.nonce(addr("e0")), 0);
+        assert_eq!(state.code(addr("e1")), &[0x00]);
+    }
+
+    #[test]
+    fn authorization_refund_for_existing_authority() {
+        // 21000 + 25000 gas used, refund 12500 capped at a fifth
+        let (mut state, block) = setup(1_000_000);
+        let result = transact(&mut state, &typed_tx(TxType::SetCode), &block, SpecId::Prague).unwrap();
+        assert_eq!(result.gas_refunded, 0);
+        let (mut state, block) = setup(1_000_000);
+        state.insert_account(addr("e0"), Account::init(0, u256::from(1u64), Vec::new()));
+        let result = transact(&mut state, &typed_tx(TxType::SetCode), &block, SpecId::Prague).unwrap();
+        assert_eq!(result.gas_refunded, 46_000 / 5);
+        assert_eq!(result.gas_used, 46_000 - 46_000 / 5);
+    }
+
+    #[test]
+    fn delegation_round_trip() {
+        let (mut state, block) = setup(1_000_000);
+        // SSTORE(0, 1) STOP
+        state.insert_account(addr("d0"), Account::init(1, u256::ZERO, hex::decode("600160005500").unwrap()));
+        let tx = Transaction { recipient: Some(addr("e0")), ..typed_tx(TxType::SetCode) };
+        assert!(transact(&mut state, &tx, &block, SpecId::Prague).unwrap().is_success());
+        assert_eq!(state.code(addr("e0")), &[&DELEGATION_PREFIX[..], &addr("d0").as_slice()].concat()[..]);
+        assert_eq!(state.delegation(addr("e0")), Some(addr("d0")));
+        assert_eq!(state.nonce(addr("e0")), 1);
+        // Calls to the authority run the delegate's code against the authority's storage
+        assert_eq!(state.sload(addr("e0"), U256BE::zero()), U256BE::from_u8(1));
+        assert_eq!(state.sload(addr("d0"), U256BE::zero()), U256BE::zero());
+
+        // Delegating to the zero address clears the delegation
+        let auth = Authorization { chain_id: 0, address: U160::default(), nonce: 1, authority: addr("e0") };
+        let tx = Transaction { nonce: 1, authorization_list: vec![auth], ..typed_tx(TxType::SetCode) };
+        assert!(transact(&mut state, &tx, &block, SpecId::Prague).unwrap().is_success());
+        assert!(state.code(addr("e0")).is_empty());
+        assert_eq!(state.delegation(addr("e0")), None);
+        assert_eq!(state.nonce(addr("e0")), 2);
+    }
+
+    #[test]
+    fn nonce_at_max_value_is_rejected() {
+        let (mut state, block) = setup(1_000_000);
+        state.set_nonce(addr("a0"), u64::MAX as usize);
+        let tx = Transaction { nonce: u64::MAX as usize, ..typed_tx(TxType::Legacy) };
+        assert_eq!(transact(&mut state, &tx, &block, SpecId::Prague).unwrap_err(), InvalidTransaction::NonceMaxValue);
+        assert_eq!(state.nonce(addr("a0")), u64::MAX as usize);
+        assert_eq!(state.balance(addr("a0")), 1_000_000);
+    }
+
+    #[test]
+    fn large_excess_blob_gas_does_not_affect_other_transactions() {
+        let (mut state, block) = setup(1_000_000);
+        let block = Block { excess_blob_gas: 1_000_000_000, ..block };
+        let tx = Transaction {
+            chain_id: Some(1),
+            sender: addr("a0"),
+            recipient: Some(addr("b0")),
+            gaslimit: 21_000,
+            maxgas: 1,
+            ..Transaction::default()
+        };
+        assert!(transact(&mut state, &tx, &block, SpecId::Cancun).unwrap().is_success());
+        assert_eq!(state.balance(addr("a0")), 1_000_000 - 21_000);
+    }
+
+    #[test]
+    fn contract_addresses() {
+        let sender = addr("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(create_address(sender, 0), addr("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"));
+        assert_eq!(create_address(sender, 1), addr("343c43a37d37dff08ae8c4a11544c718abb4fcf8"));
+        // EIP-1014 examples 0 and 1
+        assert_eq!(create2_address(addr("00"), U256BE::zero(), &[0x00]), addr("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"));
+        assert_eq!(
+            create2_address(addr("deadbeef00000000000000000000000000000000"), U256BE::zero(), &[0x00]),
+            addr("b928f69bb1d91cd65274e3c79d8986362984fda3")
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use super::analysis::JumpTable;
+use super::custom_type::{U160, U256BE};
+use super::journal::{Checkpoint, EndedTransaction, JournalEntry};
+use super::state::{AccessListItem, Account, Log};
+use super::statuscode::StatusCode;
+use super::storage::StorageSlot;
+use ethnum::u256;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Default)]
+// World state: mapping of addresses to accounts, with a journal of changes for rollback
+pub struct WorldState {
+    accounts: HashMap<U160, Account>,
+    journal: Vec<JournalEntry>,
+    // Number of open checkpoints
+    depth: usize,
+    // Storage values at the start of the current transaction, for slots written during it
+    originals: HashMap<(U160, U256BE), U256BE>,
+    // Logs emitted during the current transaction
+    logs: Vec<Log>,
+    // Transient storage, discarded at the end of each transaction (EIP-1153)
+    transient: HashMap<(U160, U256BE), U256BE>,
+    // Addresses and storage keys accessed during the current transaction (EIP-2929)
+    accessed_addresses: HashSet<U160>,
+    accessed_storage: HashSet<(U160, U256BE)>,
+    // Accounts created and accounts scheduled for deletion during the current transaction
+    created: HashSet<U160>,
+    destructed: HashSet<U160>,
+    // Jump destination analyses by code hash, shared across executions
+    jump_tables: HashMap<U256BE, Rc<JumpTable>>,
+}
+impl WorldState {
+    // Initialize empty world state
+    pub fn init() -> Self {
+        WorldState {
+            accounts: HashMap::new(),
+            journal: Vec::new(),
+            depth: 0,
+            originals: HashMap::new(),
+            logs: Vec::new(),
+            transient: HashMap::new(),
+            accessed_addresses: HashSet::new(),
+            accessed_storage: HashSet::new(),
+            created: HashSet::new(),
+            destructed: HashSet::new(),
+            jump_tables: HashMap::new(),
+        }
+    }
+
+    // Record change in journal while any checkpoint is open
+    fn record(&mut self, entry: JournalEntry) {
+        if self.depth > 0 { self.journal.push(entry); };
+    }
+
+    // Open a checkpoint that subsequent changes can be reverted to
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        let checkpoint = Checkpoint {
+            journal_len: self.journal.len(),
+            depth: self.depth,
+        };
+        self.depth += 1;
+        checkpoint
+    }
+
+    // Close checkpoint keeping its changes
+    pub fn commit(&mut self, checkpoint: Checkpoint) {
+        self.depth = checkpoint.depth;
+        if self.depth == 0 { self.journal.clear(); };
+    }
+
+    // Close checkpoint undoing every change made since it was opened
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) {
+        while self.journal.len() > checkpoint.journal_len {
+            match self.journal.pop() {
+                Some(JournalEntry::AccountChange { address, previous }) => match previous {
+                    Some(account) => { self.accounts.insert(address, account); },
+                    None => { self.accounts.remove(&address); },
+                },
+                Some(JournalEntry::BalanceChange { address, previous }) => {
+                    self.accounts.entry(address).or_default().balance = previous
+                },
+                Some(JournalEntry::NonceChange { address, previous }) => {
+                    self.accounts.entry(address).or_default().nonce = previous
+                },
+                Some(JournalEntry::CodeChange { address, previous }) => {
+                    self.accounts.entry(address).or_default().set_code(previous)
+                },
+                Some(JournalEntry::StorageChange { address, key, previous }) => {
+                    self.accounts.entry(address).or_default().storage.store(key, previous);
+                },
+                Some(JournalEntry::TransientStorageChange { address, key, previous }) => {
+                    self.transient_store(address, key, previous);
+                },
+                Some(JournalEntry::AccountCreated { address }) => { self.created.remove(&address); },
+                Some(JournalEntry::AccountDestructed { address }) => { self.destructed.remove(&address); },
+                Some(JournalEntry::TransactionEnded(ended)) => {
+                    let ended = *ended;
+                    self.originals = ended.originals;
+                    self.transient = ended.transient;
+                    self.accessed_addresses = ended.accessed_addresses;
+                    self.accessed_storage = ended.accessed_storage;
+                    self.created = ended.created;
+                    self.destructed = ended.destructed;
+                },
+                Some(JournalEntry::LogAdded) => { self.logs.pop(); },
+                Some(JournalEntry::LogsTaken { logs }) => { self.logs = logs; },
+                Some(JournalEntry::AddressWarmed { address }) => { self.accessed_addresses.remove(&address); },
+                Some(JournalEntry::StorageWarmed { address, key }) => {
+                    self.accessed_storage.remove(&(address, key));
+                },
+                None => break,
+            };
+        }
+        self.depth = checkpoint.depth;
+        if self.depth == 0 { self.journal.clear(); };
+    }
+
+    // End the current transaction: self-destructed accounts are deleted, written storage values become the
+    // new originals, transient storage is discarded and all accesses turn cold. Journaled like any other
+    // change, so an enclosing checkpoint can undo it
+    pub fn end_transaction(&mut self) {
+        let destructed: Vec<U160> = self.destructed.iter().copied().collect();
+        for address in destructed {
+            if let Some(previous) = self.accounts.remove(&address) {
+                self.record(JournalEntry::AccountChange { address, previous: Some(previous) });
+            };
+        }
+        let ended = EndedTransaction {
+            originals: std::mem::take(&mut self.originals),
+            transient: std::mem::take(&mut self.transient),
+            accessed_addresses: std::mem::take(&mut self.accessed_addresses),
+            accessed_storage: std::mem::take(&mut self.accessed_storage),
+            created: std::mem::take(&mut self.created),
+            destructed: std::mem::take(&mut self.destructed),
+        };
+        self.record(JournalEntry::TransactionEnded(Box::new(ended)));
+    }
+
+    // Record that the account at address was created in the current transaction
+    pub fn mark_created(&mut self, address: U160) {
+        if self.created.insert(address) { self.record(JournalEntry::AccountCreated { address }); };
+    }
+
+    // Returns whether the account at address was created in the current transaction
+    pub fn is_created(&self, address: U160) -> bool {
+        self.created.contains(&address)
+    }
+
+    // Schedule the account at address for deletion at the end of the transaction,
+    // returning whether it was not already scheduled
+    pub fn selfdestruct(&mut self, address: U160) -> bool {
+        let scheduled = self.destructed.insert(address);
+        if scheduled { self.record(JournalEntry::AccountDestructed { address }); };
+        scheduled
+    }
+
+    // Returns whether the account at address is scheduled for deletion
+    pub fn is_destructed(&self, address: U160) -> bool {
+        self.destructed.contains(&address)
+    }
+
+    // Mark address as accessed, returning whether it was cold
+    pub fn access_address(&mut self, address: U160) -> bool {
+        let cold = self.accessed_addresses.insert(address);
+        if cold { self.record(JournalEntry::AddressWarmed { address }); };
+        cold
+    }
+
+    // Mark storage key of account at address as accessed, returning whether it was cold
+    pub fn access_storage(&mut self, address: U160, key: U256BE) -> bool {
+        let cold = self.accessed_storage.insert((address, key));
+        if cold { self.record(JournalEntry::StorageWarmed { address, key }); };
+        cold
+    }
+
+    // Pre-warm addresses and storage keys of an access list (EIP-2930)
+    pub fn warm_access_list(&mut self, access_list: &[AccessListItem]) {
+        for item in access_list {
+            self.access_address(item.address);
+            for key in &item.storage_keys { self.access_storage(item.address, *key); }
+        }
+    }
+
+    // Return access list of everything accessed in the current transaction, sorted by address and key.
+    // Excluded addresses are omitted unless storage keys of theirs were accessed
+    pub fn access_list(&self, exclude: &[U160]) -> Vec<AccessListItem> {
+        let mut list: BTreeMap<U160, Vec<U256BE>> = BTreeMap::new();
+        for address in &self.accessed_addresses {
+            if !exclude.contains(address) { list.entry(*address).or_default(); };
+        }
+        for (address, key) in &self.accessed_storage { list.entry(*address).or_default().push(*key); }
+        list.into_iter()
+            .map(|(address, mut storage_keys)| {
+                storage_keys.sort();
+                AccessListItem { address, storage_keys }
+            })
+            .collect()
+    }
+
+    // Insert account at address, replacing any existing account
+    pub fn insert_account(&mut self, address: U160, account: Account) {
+        let previous = self.accounts.insert(address, account);
+        self.record(JournalEntry::AccountChange { address, previous });
+    }
+
+    // Return reference to account at address
+    pub fn account(&self, address: U160) -> Option<&Account> {
+        self.accounts.get(&address)
+    }
+
+    // Return mutable reference to account at address, creating it if absent
+    fn account_mut(&mut self, address: U160) -> &mut Account {
+        if !self.accounts.contains_key(&address) {
+            self.record(JournalEntry::AccountChange { address, previous: None });
+        };
+        self.accounts.entry(address).or_default()
+    }
+
+    // Returns whether an account exists at address
+    pub fn exists(&self, address: U160) -> bool {
+        self.accounts.contains_key(&address)
+    }
+
+    // Return iterator over (address, account) pairs
+    pub fn accounts(&self) -> impl Iterator<Item = (&U160, &Account)> {
+        self.accounts.iter()
+    }
+
+    // Return balance of account at address
+    pub fn balance(&self, address: U160) -> u256 {
+        self.account(address).map_or(u256::ZERO, |acc| acc.balance)
+    }
+
+    // Set balance of account at address
+    pub fn set_balance(&mut self, address: U160, balance: u256) {
+        let previous = self.account_mut(address).balance;
+        self.record(JournalEntry::BalanceChange { address, previous });
+        self.account_mut(address).balance = balance;
+    }
+
+    // Return nonce of account at address
+    pub fn nonce(&self, address: U160) -> usize {
+        self.account(address).map_or(0, |acc| acc.nonce)
+    }
+
+    // Set nonce of account at address
+    pub fn set_nonce(&mut self, address: U160, nonce: usize) {
+        let previous = self.account_mut(address).nonce;
+        self.record(JournalEntry::NonceChange { address, previous });
+        self.account_mut(address).nonce = nonce;
+    }
+
+    // Return code of account at address
+    pub fn code(&self, address: U160) -> &[u8] {
+        self.account(address).map_or(&[], |acc| acc.code())
+    }
+
+    // Return account whose code the account at address delegates to (EIP-7702)
+    pub fn delegation(&self, address: U160) -> Option<U160> {
+        self.account(address).and_then(|acc| acc.delegation())
+    }
+
+    // Set code of account at address
+    pub fn set_code(&mut self, address: U160, code: Vec<u8>) {
+        let previous = self.account_mut(address).code().to_vec();
+        self.record(JournalEntry::CodeChange { address, previous });
+        self.account_mut(address).set_code(code);
+    }
+
+    // Return jump destination analysis of account code at address, analyzing it on first use
+    pub fn jump_table(&mut self, address: U160) -> Rc<JumpTable> {
+        let (codehash, code) = match self.account(address) {
+            Some(acc) => (acc.codehash(), acc.code()),
+            None => return Rc::new(JumpTable::analyze(&[])),
+        };
+        if let Some(table) = self.jump_tables.get(&codehash) { return table.clone(); };
+        let table = Rc::new(JumpTable::analyze(code));
+        self.jump_tables.insert(codehash, table.clone());
+        table
+    }
+
+    // Return code hash of account at address, zero for non-existent or empty accounts (EIP-1052)
+    pub fn codehash(&self, address: U160) -> U256BE {
+        match self.account(address) {
+            Some(acc) if !acc.is_empty() => acc.codehash(),
+            _ => U256BE::zero(),
+        }
+    }
+
+    // Return storage slot state of account at address
+    pub fn storage_slot(&self, address: U160, key: U256BE) -> StorageSlot {
+        let current = self.sload(address, key);
+        StorageSlot {
+            original: self.originals.get(&(address, key)).copied().unwrap_or(current),
+            current,
+        }
+    }
+
+    // Load current storage value of account at address
+    pub fn sload(&self, address: U160, key: U256BE) -> U256BE {
+        self.account(address)
+            .map_or(U256BE::zero(), |acc| acc.storage.load(key))
+    }
+
+    // Store value in storage of account at address
+    pub fn sstore(&mut self, address: U160, key: U256BE, value: U256BE) {
+        let previous = self.account_mut(address).storage.store(key, value);
+        self.originals.entry((address, key)).or_insert(previous);
+        self.record(JournalEntry::StorageChange { address, key, previous });
+    }
+
+    // Load transient storage value of account at address
+    pub fn tload(&self, address: U160, key: U256BE) -> U256BE {
+        self.transient.get(&(address, key)).copied().unwrap_or_default()
+    }
+
+    // Store value in transient storage of account at address
+    pub fn tstore(&mut self, address: U160, key: U256BE, value: U256BE) {
+        let previous = self.transient_store(address, key, value);
+        self.record(JournalEntry::TransientStorageChange { address, key, previous });
+    }
+
+    // Write transient storage slot, keeping only non-zero values, returning the previous value
+    fn transient_store(&mut self, address: U160, key: U256BE, value: U256BE) -> U256BE {
+        let previous = match value.is_zero() {
+            true => self.transient.remove(&(address, key)),
+            false => self.transient.insert((address, key), value),
+        };
+        previous.unwrap_or_default()
+    }
+
+    // Append log to the current transaction
+    pub fn log(&mut self, log: Log) {
+        self.logs.push(log);
+        self.record(JournalEntry::LogAdded);
+    }
+
+    // Return logs emitted during the current transaction
+    pub fn logs(&self) -> &[Log] {
+        &self.logs
+    }
+
+    // Remove and return logs emitted during the current transaction
+    pub fn take_logs(&mut self) -> Vec<Log> {
+        let logs = std::mem::take(&mut self.logs);
+        self.record(JournalEntry::LogsTaken { logs: logs.clone() });
+        logs
+    }
+
+    // Transfer value between accounts, failing if sender balance is insufficient
+    pub fn transfer(&mut self, from: U160, to: U160, value: u256) -> Result<(), StatusCode> {
+        if value == u256::ZERO { return Ok(()); };
+        let balance = self.balance(from);
+        if balance < value { return Err(StatusCode::InsufficientBalance); };
+        self.set_balance(from, balance - value);
+        self.set_balance(to, self.balance(to) + value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::execution::ExecutionContext;
+    use crate::lib::memory::Memory;
+    use crate::lib::message::Message;
+    use crate::lib::spec::SpecId;
+    use crate::lib::stack::Stack;
+    use crate::lib::state::{Block, Transaction};
+    use crate::lib::transact::transact;
+
+    fn addr(byte: u8) -> U160 {
+        U160::from_slice(&[byte])
+    }
+
+    #[test]
+    fn transient_storage_is_journaled_and_cleared_at_transaction_end() {
+        let mut state = WorldState::init();
+        let (key, one, two) = (U256BE::zero(), U256BE::from_u8(1), U256BE::from_u8(2));
+        state.tstore(addr(0x10), key, one);
+        let checkpoint = state.checkpoint();
+        state.tstore(addr(0x10), key, two);
+        state.tstore(addr(0x20), key, two);
+        state.revert_to(checkpoint);
+        assert_eq!(state.tload(addr(0x10), key), one);
+        assert_eq!(state.tload(addr(0x20), key), U256BE::zero());
+        state.end_transaction();
+        assert_eq!(state.tload(addr(0x10), key), U256BE::zero());
+    }
+
+    #[test]
+    fn revert_restores_self_destructed_account() {
+        let mut state = WorldState::init();
+        // SELFDESTRUCT(0x77)
+        let code = hex::decode("6077ff").unwrap();
+        state.insert_account(addr(0x10), Account::init(1, u256::from(100u64), code.clone()));
+        let checkpoint = state.checkpoint();
+        let message = Message::call(addr(0x01), addr(0x10), u256::ZERO, Vec::new(), 100_000);
+        let result = ExecutionContext::init(message, Stack::init(), Memory::init(), &mut state, SpecId::Shanghai).run();
+        assert!(result.is_success());
+        assert!(!state.exists(addr(0x10)));
+        assert_eq!(state.balance(addr(0x77)), 100);
+
+        state.revert_to(checkpoint);
+        let account = state.account(addr(0x10)).unwrap();
+        assert_eq!(account.balance, 100);
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.code(), &code[..]);
+        assert!(!state.exists(addr(0x77)));
+        assert!(!state.is_destructed(addr(0x10)));
+    }
+
+    #[test]
+    fn revert_restores_state_after_nested_transactions() {
+        let mut state = WorldState::init();
+        state.insert_account(addr(0xa0), Account::init(0, u256::from(1_000_000u64), Vec::new()));
+        // SSTORE(0, 1) LOG0(0, 0) STOP
+        let code = hex::decode("600160005560006000a000").unwrap();
+        state.insert_account(addr(0xb0), Account::init(1, u256::ZERO, code.clone()));
+        state.log(Log { address: addr(0xb0), topics: Vec::new(), data: vec![0x01] });
+        state.access_address(addr(0xe0));
+        let block = Block::default();
+        let call = Transaction {
+            chain_id: Some(1),
+            sender: addr(0xa0),
+            recipient: Some(addr(0xb0)),
+            value: u256::from(10u64),
+            gaslimit: 100_000,
+            maxgas: 1,
+            ..Transaction::default()
+        };
+        // MSTORE8(0, 0x01) RETURN(0, 1)
+        let create = Transaction {
+            nonce: 1,
+            recipient: None,
+            data: hex::decode("600160005360016000f3").unwrap(),
+            ..call.clone()
+        };
+
+        let checkpoint = state.checkpoint();
+        assert!(transact(&mut state, &call, &block, SpecId::Prague).unwrap().is_success());
+        let created = transact(&mut state, &create, &block, SpecId::Prague).unwrap().created_address.unwrap();
+        assert_eq!(state.sload(addr(0xb0), U256BE::zero()), U256BE::from_u8(1));
+        assert_eq!(state.code(created), &[0x01]);
+        assert!(state.logs().is_empty());
+        state.revert_to(checkpoint);
+
+        assert_eq!(state.balance(addr(0xa0)), 1_000_000);
+        assert_eq!(state.nonce(addr(0xa0)), 0);
+        assert_eq!(state.balance(addr(0xb0)), 0);
+        assert_eq!(state.sload(addr(0xb0), U256BE::zero()), U256BE::zero());
+        assert_eq!(state.code(addr(0xb0)), &code[..]);
+        assert!(!state.exists(created));
+        assert_eq!(state.logs().len(), 1);
+        assert_eq!(state.logs()[0].data, vec![0x01]);
+        assert!(!state.access_address(addr(0xe0)));
+        assert!(state.access_address(addr(0xb0)));
+    }
+}
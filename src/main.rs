@@ -1,21 +1,70 @@
+#![allow(special_module_name)]
 pub mod lib;
-use hex;
 use lib::execution::ExecutionContext;
 use lib::memory::Memory;
+use lib::message::Message;
+use lib::spec::SpecId;
 use lib::stack::Stack;
 use lib::statuscode::StatusCode;
+use lib::custom_type::U160;
+use lib::state::{Account, Block};
+use lib::world_state::WorldState;
+use ethnum::u256;
 use std::env;
+use std::fs;
 
-// cargo run {gas_limit} {bytecode}
+// Remove flag and its value from args, returning the value
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == flag)?;
+    let value = args.get(i + 1).cloned().expect("missing flag value");
+    args.drain(i..i + 2);
+    Some(value)
+}
+
+// cargo run {gas_limit} {bytecode} [fork] [--calldata {hex}] [--block {json or json file}] [--trace]
 fn main() -> Result<(), StatusCode> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let trace = match args.iter().position(|arg| arg == "--trace") {
+        Some(i) => { args.remove(i); true },
+        None => false,
+    };
+    let calldata = match take_flag(&mut args, "--calldata") {
+        Some(calldata) => hex::decode(calldata).unwrap(),
+        None => Vec::new(),
+    };
+    let block = match take_flag(&mut args, "--block") {
+        Some(block) => {
+            let json = fs::read_to_string(&block).unwrap_or(block);
+            Block::from_json(&json).expect("invalid block json")
+        },
+        None => Block::default(),
+    };
     let stack = Stack::init();
     let memory = Memory::init();
-    let mut executor = ExecutionContext::init(
-        hex::decode(&args[2]).unwrap(),
-        stack,
-        memory,
+    let address = U160::from_slice(&[0x10]);
+    let mut state = WorldState::init();
+    state.insert_account(address, Account::init(1, u256::ZERO, hex::decode(&args[2]).unwrap()));
+    let message = Message::call(
+        U160::from_slice(&[0x01]),
+        address,
+        u256::ZERO,
+        calldata,
         str::parse::<usize>(&args[1]).unwrap(),
     );
-    executor.run()
+    let spec = match args.get(3) {
+        Some(name) => SpecId::from_name(name).expect("unknown fork name"),
+        None => SpecId::default(),
+    };
+    let mut executor = ExecutionContext::init(message, stack, memory, &mut state, spec);
+    executor.set_block(block);
+    executor.set_trace(trace);
+    let result = executor.run();
+    println!(
+        "[ Result: {:?} | Gas Used: {} | Gas Refunded: {} ]\nReturn Data: {}",
+        result.reason,
+        result.gas_used,
+        result.gas_refunded,
+        hex::encode(&result.output)
+    );
+    result.status()
 }